@@ -0,0 +1,477 @@
+use advent_of_code_2019::*;
+use advent_of_code_2019::log::{Filter, Level};
+use advent_of_code_2019::runner::*;
+use std::str::FromStr;
+
+// A run failed: a panic, a wrong answer, a regression or a part that is missing
+pub const EXIT_FAILURE:i32 = 1;
+// The command line could not be parsed
+pub const EXIT_USAGE:i32 = 2;
+// The puzzle input could not be found or read
+pub const EXIT_INPUT:i32 = 3;
+
+pub const USAGE:&str = "\
+usage: advent_of_code_2019 [-v|-vv|-vvv|-q] [--log <filter>] <command> [<args>]
+
+commands:
+  solve <day> [1|2|both]   solve a day, both implemented parts by default
+  list                     list the days and their implemented parts
+  all                      solve every day and show a timing table
+  verify [<day>..]         compare answers with answers.toml
+  record [<day>..]         write answers to answers.toml
+  bench <day> <part>       time a part and compare it with the baseline
+  intcode <file>           run an Intcode program
+  render <day> [1|2]       solve a day and draw a picture answer in blocks
+  help [<command>]         show this text or the options of a command
+
+`<day> <part>` without a command is short for `solve <day> <part>`.
+
+logging:
+  -v, -vv, -vvv            show info, debug or trace messages on stderr
+  -q                       show errors only
+  --log <filter>           levels per day, such as info,day13=trace
+
+exit codes:
+  0 success, 1 a run failed, 2 bad arguments, 3 missing or unreadable input
+";
+
+const SOLVE_USAGE:&str = "\
+usage: solve <day> [1|2|both] [--input <path> | --example <text>] [--format text|json]
+
+  --input <path>      read the puzzle input from a file
+  --example <text>    use the text as input, \\n starts a new line
+  --format json       print one JSON object per part
+
+Without --input the input is read from stdin when it is piped, otherwise
+input_<day>.txt is looked up here or in an inputs directory.
+";
+
+const LIST_USAGE:&str = "\
+usage: list
+";
+
+const ALL_USAGE:&str = "\
+usage: all [--parallel] [--fail-on-panic] [--format text|json]
+
+  --parallel          solve on all cores
+  --fail-on-panic     exit with 1 when a solver panics
+  --format json       print one JSON object per day and part
+";
+
+const VERIFY_USAGE:&str = "\
+usage: verify|record [<day>..] [--parallel]
+
+verify runs the given days, or all recorded days, and compares the answers
+with answers.toml. record runs the given days, or all days, and writes the
+answers to answers.toml.
+";
+
+const BENCH_USAGE:&str = "\
+usage: bench <day> <part> [--input <path>] [--runs <n>] [--warmup <n>] [--threshold <percent>] [--save]
+
+  --runs <n>              measured runs, 10 by default
+  --warmup <n>            unmeasured runs first, 1 by default
+  --threshold <percent>   slowdown of the median that fails, 10 by default
+  --save                  store the result in bench_baseline.txt
+";
+
+const INTCODE_USAGE:&str = "\
+usage: intcode <file> [--input <n,n,..>] [--ascii] [--gdb <address>]
+
+  --input <n,n,..>    input values, comma separated
+  --ascii             send --input as text and print outputs as text
+  --gdb <address>     wait for a gdb remote connection, such as localhost:1234
+";
+
+const RENDER_USAGE:&str = "\
+usage: render <day> [1|2] [--input <path> | --example <text>]
+
+Draws picture answers, such as the letters of day 8 and 11, with blocks.
+Part 2 by default.
+";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Text,
+    Json,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    // No parts means the implemented ones, no source the default one
+    Solve{day:u8, parts:Option<Vec<Part>>, source:Option<InputSource>, format:Format},
+    List,
+    All{threads:usize, fail_on_panic:bool, format:Format},
+    Verify{days:Vec<u8>, threads:usize, record:bool},
+    Bench{day:u8, part:Part, source:InputSource, runs:usize, warmup:usize, threshold:f64, save:bool},
+    Intcode{path:String, inputs:Vec<i64>, ascii:bool, gdb:Option<String>},
+    Render{day:u8, part:Part, source:Option<InputSource>},
+    Help(&'static str),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cli {
+    pub command:Command,
+    pub log:Filter,
+}
+
+struct Args<'a> {
+    iter:std::slice::Iter<'a, String>,
+}
+
+impl<'a> Args<'a> {
+    fn next(&mut self) -> Option<&'a str> {
+        self.iter.next().map(String::as_str)
+    }
+
+    fn value(&mut self, option:&str) -> Result<&'a str, String> {
+        self.next().ok_or_else(|| format!("{} needs a value", option))
+    }
+
+    fn number<T:FromStr>(&mut self, option:&str) -> Result<T, String> {
+        let value = self.value(option)?;
+        value.parse().map_err(|_| format!("{} expects a number, got '{}'", option, value))
+    }
+}
+
+pub fn parse(args:&[String]) -> Result<Cli, String> {
+    let (args, log) = parse_logging(args)?;
+
+    let command = match args.first().map(String::as_str) {
+        None => Command::Help(USAGE),
+        Some("-h") | Some("--help") => Command::Help(USAGE),
+        Some("help") => Command::Help(match args.get(1) {
+            Some(command) => command_usage(command).ok_or_else(|| format!("unknown command '{}'", command))?,
+            None => USAGE,
+        }),
+        Some(command) if args[1..].iter().any(|arg| arg == "-h" || arg == "--help") =>
+            Command::Help(command_usage(command).unwrap_or(USAGE)),
+        Some("solve") => parse_solve(&args[1..])?,
+        Some("list") => parse_list(&args[1..])?,
+        Some("all") => parse_all(&args[1..])?,
+        Some("verify") => parse_verify(&args[1..], false)?,
+        Some("record") => parse_verify(&args[1..], true)?,
+        Some("bench") => parse_bench(&args[1..])?,
+        Some("intcode") => parse_intcode(&args[1..])?,
+        Some("render") => parse_render(&args[1..])?,
+        Some(day) if day.parse::<u8>().is_ok() => parse_solve(&args)?,
+        Some(command) => return Err(format!("unknown command '{}'", command)),
+    };
+
+    Ok(Cli{command, log})
+}
+
+fn command_usage(command:&str) -> Option<&'static str> {
+    match command {
+        "solve" => Some(SOLVE_USAGE),
+        "list" => Some(LIST_USAGE),
+        "all" => Some(ALL_USAGE),
+        "verify" | "record" => Some(VERIFY_USAGE),
+        "bench" => Some(BENCH_USAGE),
+        "intcode" => Some(INTCODE_USAGE),
+        "render" => Some(RENDER_USAGE),
+        _ => None,
+    }
+}
+
+// Takes the log options in front of the command. Later arguments belong to the
+// command, where they may be values such as `--input -q`.
+fn parse_logging(args:&[String]) -> Result<(Vec<String>, Filter), String> {
+    let mut level = Level::Warn;
+    let mut spec = String::new();
+
+    let mut args = Args{iter:args.iter()};
+    let rest = loop {
+        let rest = args.iter.as_slice();
+        match args.next() {
+            Some("-v") => level = Level::Info,
+            Some("-vv") => level = Level::Debug,
+            Some("-vvv") => level = Level::Trace,
+            Some("-q") => level = Level::Error,
+            Some("--log") => spec = String::from(args.value("--log")?),
+            _ => break rest.to_vec(),
+        }
+    };
+
+    Ok((rest, Filter::parse(&spec, level)?))
+}
+
+fn parse_day(text:&str) -> Result<u8, String> {
+    let day = match text.parse::<u8>() {
+        Ok(day) if (1..=25).contains(&day) => day,
+        _ => return Err(format!("'{}' is not a day between 1 and 25", text)),
+    };
+    match find_solver(day) {
+        Some(_) => Ok(day),
+        None => Err(SolveError::UnknownDay(day).to_string()),
+    }
+}
+
+fn parse_part(text:&str) -> Result<Vec<Part>, String> {
+    match text {
+        "1" => Ok(vec![Part::Part1]),
+        "2" => Ok(vec![Part::Part2]),
+        "both" => Ok(vec![Part::Part1, Part::Part2]),
+        _ => Err(format!("'{}' is not a part, expected 1, 2 or both", text)),
+    }
+}
+
+fn parse_single_part(text:&str) -> Result<Part, String> {
+    match parse_part(text)?.as_slice() {
+        [part] => Ok(*part),
+        _ => Err(String::from("expected part 1 or 2")),
+    }
+}
+
+fn parse_format(text:&str) -> Result<Format, String> {
+    match text {
+        "text" => Ok(Format::Text),
+        "json" => Ok(Format::Json),
+        _ => Err(format!("unknown format '{}', expected text or json", text)),
+    }
+}
+
+fn parallelism() -> usize {
+    std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1)
+}
+
+// Splits off the positional arguments, options with their values stay
+fn positionals<'a>(args:&'a [String], with_value:&[&str]) -> (Vec<&'a str>, Vec<&'a String>) {
+    let mut positionals = vec![];
+    let mut options = vec![];
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg.starts_with("--") {
+            options.push(arg);
+            if with_value.contains(&arg.as_str()) {
+                options.extend(args.next());
+            }
+        } else {
+            positionals.push(arg.as_str());
+        }
+    }
+    (positionals, options)
+}
+
+fn parse_source(option:&str, args:&mut Args) -> Result<InputSource, String> {
+    match option {
+        "--input" => Ok(InputSource::File(String::from(args.value(option)?))),
+        _ => Ok(InputSource::Inline(String::from(args.value(option)?))),
+    }
+}
+
+fn parse_solve(args:&[String]) -> Result<Command, String> {
+    let (positionals, options) = positionals(args, &["--input", "--example", "--format"]);
+    let (day, parts) = match positionals.as_slice() {
+        [day] => (parse_day(day)?, None),
+        [day, part] => (parse_day(day)?, Some(parse_part(part)?)),
+        [] => return Err(String::from("solve needs a day")),
+        _ => return Err(format!("unexpected argument '{}'", positionals[2])),
+    };
+
+    let mut source = None;
+    let mut format = Format::Text;
+    let options:Vec<String> = options.into_iter().cloned().collect();
+    let mut args = Args{iter:options.iter()};
+    while let Some(option) = args.next() {
+        match option {
+            "--input" | "--example" => source = Some(parse_source(option, &mut args)?),
+            "--format" => format = parse_format(args.value(option)?)?,
+            _ => return Err(format!("unknown option {}", option)),
+        }
+    }
+
+    Ok(Command::Solve{day, parts, source, format})
+}
+
+fn parse_list(args:&[String]) -> Result<Command, String> {
+    match args.first() {
+        None => Ok(Command::List),
+        Some(arg) => Err(format!("unexpected argument '{}'", arg)),
+    }
+}
+
+fn parse_all(args:&[String]) -> Result<Command, String> {
+    let mut threads = 1;
+    let mut fail_on_panic = false;
+    let mut format = Format::Text;
+
+    let mut args = Args{iter:args.iter()};
+    while let Some(option) = args.next() {
+        match option {
+            "--parallel" => threads = parallelism(),
+            "--fail-on-panic" => fail_on_panic = true,
+            "--format" => format = parse_format(args.value(option)?)?,
+            _ => return Err(format!("unknown option {}", option)),
+        }
+    }
+
+    Ok(Command::All{threads, fail_on_panic, format})
+}
+
+fn parse_verify(args:&[String], record:bool) -> Result<Command, String> {
+    let mut days = vec![];
+    let mut threads = 1;
+
+    let mut args = Args{iter:args.iter()};
+    while let Some(arg) = args.next() {
+        match arg {
+            "--parallel" => threads = parallelism(),
+            option if option.starts_with("--") => return Err(format!("unknown option {}", option)),
+            day => days.push(parse_day(day)?),
+        }
+    }
+
+    Ok(Command::Verify{days, threads, record})
+}
+
+fn parse_bench(args:&[String]) -> Result<Command, String> {
+    let (positionals, options) = positionals(args, &["--input", "--runs", "--warmup", "--threshold"]);
+    let (day, part) = match positionals.as_slice() {
+        [day, part] => (parse_day(day)?, parse_single_part(part)?),
+        _ => return Err(String::from("bench needs a day and a part")),
+    };
+
+    let mut source = InputSource::Discover(String::from("."));
+    let mut runs = 10;
+    let mut warmup = 1;
+    let mut threshold = 10.0;
+    let mut save = false;
+    let options:Vec<String> = options.into_iter().cloned().collect();
+    let mut args = Args{iter:options.iter()};
+    while let Some(option) = args.next() {
+        match option {
+            "--input" => source = parse_source(option, &mut args)?,
+            "--runs" => runs = args.number(option)?,
+            "--warmup" => warmup = args.number(option)?,
+            "--threshold" => threshold = args.number(option)?,
+            "--save" => save = true,
+            _ => return Err(format!("unknown option {}", option)),
+        }
+    }
+    if runs == 0 {
+        return Err(String::from("--runs must be at least 1"));
+    }
+
+    Ok(Command::Bench{day, part, source, runs, warmup, threshold, save})
+}
+
+fn parse_intcode(args:&[String]) -> Result<Command, String> {
+    let (positionals, options) = positionals(args, &["--input", "--gdb"]);
+    let path = match positionals.as_slice() {
+        [path] => String::from(*path),
+        [] => return Err(String::from("intcode needs a program file")),
+        _ => return Err(format!("unexpected argument '{}'", positionals[1])),
+    };
+
+    let mut input = String::new();
+    let mut ascii = false;
+    let mut gdb = None;
+    let options:Vec<String> = options.into_iter().cloned().collect();
+    let mut args = Args{iter:options.iter()};
+    while let Some(option) = args.next() {
+        match option {
+            "--input" => input = String::from(args.value(option)?),
+            "--ascii" => ascii = true,
+            "--gdb" => gdb = Some(String::from(args.value(option)?)),
+            _ => return Err(format!("unknown option {}", option)),
+        }
+    }
+
+    let inputs = if ascii {
+        unescape_example(&input).chars().map(|ch| ch as i64).collect()
+    } else {
+        input.split(',').map(str::trim).filter(|value| !value.is_empty())
+            .map(|value| value.parse().map_err(|_| format!("--input expects numbers, got '{}'", value)))
+            .collect::<Result<Vec<i64>, String>>()?
+    };
+
+    Ok(Command::Intcode{path, inputs, ascii, gdb})
+}
+
+fn parse_render(args:&[String]) -> Result<Command, String> {
+    let (positionals, options) = positionals(args, &["--input", "--example"]);
+    let (day, part) = match positionals.as_slice() {
+        [day] => (parse_day(day)?, Part::Part2),
+        [day, part] => (parse_day(day)?, parse_single_part(part)?),
+        _ => return Err(String::from("render needs a day")),
+    };
+
+    let mut source = None;
+    let options:Vec<String> = options.into_iter().cloned().collect();
+    let mut args = Args{iter:options.iter()};
+    while let Some(option) = args.next() {
+        match option {
+            "--input" | "--example" => source = Some(parse_source(option, &mut args)?),
+            _ => return Err(format!("unknown option {}", option)),
+        }
+    }
+
+    Ok(Command::Render{day, part, source})
+}
+
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope.
+    use super::*;
+
+    fn parse_line(line:&str) -> Result<Cli, String> {
+        parse(&line.split_whitespace().map(String::from).collect::<Vec<String>>())
+    }
+
+    fn command(line:&str) -> Command {
+        parse_line(line).unwrap().command
+    }
+
+    #[test]
+    fn test_solve() {
+        assert_eq!(command("solve 7"), Command::Solve{day:7, parts:None, source:None, format:Format::Text});
+        assert_eq!(command("7 2 --format json --input x.txt"),
+                   Command::Solve{day:7, parts:Some(vec![Part::Part2]), source:Some(InputSource::File(String::from("x.txt"))),
+                                  format:Format::Json});
+        assert_eq!(command("solve 6 both --example a\\nb"),
+                   Command::Solve{day:6, parts:Some(vec![Part::Part1, Part::Part2]),
+                                  source:Some(InputSource::Inline(String::from("a\\nb"))), format:Format::Text});
+    }
+
+    #[test]
+    fn test_validation() {
+        assert_eq!(parse_line("solve x").unwrap_err(), "'x' is not a day between 1 and 25");
+        assert_eq!(parse_line("solve 26").unwrap_err(), "'26' is not a day between 1 and 25");
+        assert_eq!(parse_line("solve 22").unwrap_err(), "there is no solution for day 22");
+        assert_eq!(parse_line("solve 7 3").unwrap_err(), "'3' is not a part, expected 1, 2 or both");
+        assert_eq!(parse_line("bench 7 both").unwrap_err(), "expected part 1 or 2");
+        assert_eq!(parse_line("bench 7 1 --runs many").unwrap_err(), "--runs expects a number, got 'many'");
+        assert_eq!(parse_line("all --fast").unwrap_err(), "unknown option --fast");
+        assert_eq!(parse_line("frobnicate").unwrap_err(), "unknown command 'frobnicate'");
+        assert_eq!(parse_line("solve 7 --input").unwrap_err(), "--input needs a value");
+    }
+
+    #[test]
+    fn test_help_and_logging() {
+        assert_eq!(command(""), Command::Help(USAGE));
+        assert_eq!(command("--help"), Command::Help(USAGE));
+        assert_eq!(command("help bench"), Command::Help(BENCH_USAGE));
+        assert_eq!(command("verify 7 --help"), Command::Help(VERIFY_USAGE));
+
+        let cli = parse_line("-vv --log day07=trace verify 7 8 --parallel").unwrap();
+        assert!(matches!(cli.command, Command::Verify{ref days, record:false, ..} if days == &[7, 8]));
+        assert_eq!(cli.log, Filter{level:Level::Debug, targets:vec![(String::from("day_07"), Level::Trace)]});
+
+        // Log options after the command are arguments of the command
+        let cli = parse_line("intcode prog.txt --ascii --input -q").unwrap();
+        assert_eq!(cli.command, Command::Intcode{path:String::from("prog.txt"), inputs:vec![45, 113], ascii:true, gdb:None});
+        assert_eq!(cli.log, Filter::new(Level::Warn));
+        assert!(parse_line("verify 7 -v").is_err());
+    }
+
+    #[test]
+    fn test_intcode() {
+        assert_eq!(command("intcode prog.txt --input 1,-2"),
+                   Command::Intcode{path:String::from("prog.txt"), inputs:vec![1, -2], ascii:false, gdb:None});
+        assert_eq!(command("intcode prog.txt --ascii --input A\\n"),
+                   Command::Intcode{path:String::from("prog.txt"), inputs:vec![65, 10], ascii:true, gdb:None});
+        assert!(parse_line("intcode prog.txt --input 1,x").is_err());
+    }
+}
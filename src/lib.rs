@@ -0,0 +1,39 @@
+// Solutions to Advent of Code 2019 together with the Intcode computer and the
+// tooling around them. The binary is a thin command line front end for this
+// library.
+
+mod util;
+
+pub use util::Part;
+pub use util::{Answer, InputKind, SolveError, Solver, find_solver, get_solution, solvers};
+pub use util::log;
+
+// Running solvers against inputs: discovery of input files, the run-all table
+// and JSON output, recorded answers and benchmarks
+pub mod runner {
+    pub use crate::util::{Answers, Check, ANSWERS_FILE, format_checks, part_number};
+    pub use crate::util::{Baseline, BenchStats, CountingAllocator, Regression, BASELINE_FILE, bench, format_bench};
+    pub use crate::util::{InputError, InputSource, default_source, find_input, read_input, unescape_example};
+    pub use crate::util::{RunResult, Status, format_duration, format_json, format_table, run_all, run_with_input};
+}
+
+// The Intcode virtual machine. `Program` and friends live at the top, the tools
+// built on it in submodules.
+pub mod intcode {
+    pub use crate::util::int_code_computer::*;
+    pub use crate::util::int_code_cache as cache;
+    pub use crate::util::int_code_callstack as callstack;
+    pub use crate::util::int_code_fuzz as fuzz;
+    pub use crate::util::int_code_gdb as gdb;
+    pub use crate::util::int_code_memory as memory;
+    pub use crate::util::int_code_observer as observer;
+    pub use crate::util::int_code_opcodes as opcodes;
+    pub use crate::util::int_code_optimizer as optimizer;
+    pub use crate::util::int_code_replay as replay;
+    pub use crate::util::int_code_scheduler as scheduler;
+    pub use crate::util::int_code_search as search;
+    pub use crate::util::int_code_symbolic as symbolic;
+}
+
+pub use util::grid;
+pub use util::rng;
@@ -1,33 +1,261 @@
-use std::io::prelude::*;
+use advent_of_code_2019::*;
+use advent_of_code_2019::intcode::{Program, StopReason};
+use advent_of_code_2019::intcode::gdb::GdbStub;
+use advent_of_code_2019::runner::*;
+use std::convert::TryFrom;
 use std::env;
 
-mod util;
+mod cli;
 
-use util::*;
+use cli::*;
+
+#[global_allocator]
+static ALLOCATOR:CountingAllocator = CountingAllocator;
 
 fn main() {
 
-    let args: Vec<String> = env::args().collect();
+    let args:Vec<String> = env::args().skip(1).collect();
+
+    let cli = match cli::parse(&args) {
+        Ok(cli) => cli,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            eprintln!("run with --help for usage");
+            std::process::exit(EXIT_USAGE);
+        }
+    };
+    log::set_filter(cli.log);
+
+    let code = match cli.command {
+        Command::Help(text) => {
+            print!("{}", text);
+            0
+        },
+        Command::Solve{day, parts, source, format} => solve_day(day, parts, source, format),
+        Command::List => list_days(),
+        Command::All{threads, fail_on_panic, format} => run_all_days(threads, fail_on_panic, format),
+        Command::Verify{days, threads, record} => check_answers(days, threads, record),
+        Command::Bench{day, part, source, runs, warmup, threshold, save} =>
+            bench_day(day, part, source, runs, warmup, threshold, save),
+        Command::Intcode{path, inputs, ascii, gdb} => run_intcode(&path, inputs, ascii, gdb),
+        Command::Render{day, part, source} => render_day(day, part, source),
+    };
+    std::process::exit(code);
+
+}
+
+fn fail<E:std::fmt::Display>(code:i32, err:E) -> i32 {
+    eprintln!("{}", err);
+    code
+}
+
+fn load_input(source:Option<InputSource>, day:u8) -> Result<String, i32> {
+    read_input(&source.unwrap_or_else(|| default_source(".")), day).map_err(|err| fail(EXIT_INPUT, err))
+}
+
+// Solves the parts with one read of the input. A single part prints the bare
+// answer, several parts are labelled.
+fn solve_day(day:u8, parts:Option<Vec<Part>>, source:Option<InputSource>, format:Format) -> i32 {
+    let solver = find_solver(day).expect("day is validated by the parser");
+    let parts = parts.unwrap_or_else(|| solver.parts().to_vec());
+    if parts.is_empty() {
+        return fail(EXIT_FAILURE, format!("day {} has no implemented parts", day));
+    }
+    let input = match load_input(source, day) {
+        Ok(input) => input,
+        Err(code) => return code,
+    };
+
+    let results:Vec<RunResult> = parts.iter().map(|part| run_with_input(solver, *part, input.clone())).collect();
+    if format == Format::Json {
+        print!("{}", format_json(&results));
+    } else {
+        for result in results.iter() {
+            match &result.status {
+                Status::Solved(answer) if results.len() == 1 => println!("{}", answer),
+                Status::Solved(Answer::Art(art)) => println!("part {}:\n{}", result.part, art),
+                Status::Solved(answer) => println!("part {}: {}", result.part, answer),
+                Status::NotImplemented => eprintln!("{}", SolveError::NotImplemented(day, result.part)),
+                Status::Panicked(message) => eprintln!("day {} part {} panicked: {}", day, result.part, message),
+                Status::MissingInput => unreachable!(),
+            }
+        }
+    }
 
-    if args.len() != 3 {
-        println!("program <day> <part>");
-        std::process::exit(1);
+    match results.iter().all(|result| matches!(result.status, Status::Solved(_))) {
+        true => 0,
+        false => EXIT_FAILURE,
     }
+}
+
+fn list_days() -> i32 {
+    for solver in solvers() {
+        let parts:Vec<String> = solver.parts().iter().map(|part| part.to_string()).collect();
+        println!("{:>2}  {:<36} {:<9} parts: {}", solver.day(), solver.title(),
+                 format!("{:?}", solver.input_kind()),
+                 if parts.is_empty() { String::from("none") } else { parts.join(", ") });
+    }
+    0
+}
 
-    // Read arguments
-    let day = args[1].parse::<u8>().unwrap();
-    let part = match args[2].parse::<u8>() {
-        Ok(1) => Part::Part1,
-        Ok(2) => Part::Part2,
-        _ => panic!("illegal part arguments!")
+fn run_all_days(threads:usize, fail_on_panic:bool, format:Format) -> i32 {
+    let start = std::time::Instant::now();
+    let results = run_all(&solvers(), ".", threads);
+    match format {
+        Format::Json => print!("{}", format_json(&results)),
+        Format::Text => print!("{}", format_table(&results, start.elapsed())),
+    }
+
+    let panicked = results.iter().any(|result| matches!(result.status, Status::Panicked(_)));
+    match fail_on_panic && panicked {
+        true => EXIT_FAILURE,
+        false => 0,
+    }
+}
+
+// Runs the given days, or all days recorded in the answers file, and compares
+// the answers. With `record` the answers file is updated instead.
+fn check_answers(mut days:Vec<u8>, threads:usize, record:bool) -> i32 {
+    let loaded = match record {
+        true => Answers::load_or_default(ANSWERS_FILE),
+        false => Answers::load(ANSWERS_FILE),
+    };
+    let mut answers = match loaded {
+        Ok(answers) => answers,
+        Err(err) => return fail(EXIT_INPUT, err),
     };
 
-    // Read input
-    let mut input = String::new();
-    std::io::stdin().read_to_string(&mut input).expect("Failed to fetch input...");
+    if days.is_empty() && !record {
+        days = answers.days();
+    }
+    let selected:Vec<&'static dyn Solver> = solvers().into_iter()
+        .filter(|solver| days.is_empty() || days.contains(&solver.day()))
+        .collect();
+    let results = run_all(&selected, ".", threads);
 
-    let result = get_solution(day, part, input);
+    if record {
+        let changed = answers.record(&results);
+        if let Err(err) = answers.save(ANSWERS_FILE) {
+            return fail(EXIT_FAILURE, err);
+        }
+        println!("recorded {} changed answers in {}", changed, ANSWERS_FILE);
+        return 0;
+    }
 
-    println!("{}",result);
+    let (report, failures) = format_checks(&results, &answers);
+    print!("{}", report);
+    if failures > 0 {
+        println!("{} of {} answers failed", failures, results.len());
+        return EXIT_FAILURE;
+    }
+    0
+}
 
+// Times one part and compares the median with the baseline file. Fails when it
+// is slower than the baseline by more than the threshold. With `save` the
+// baseline is updated instead.
+fn bench_day(day:u8, part:Part, source:InputSource, runs:usize, warmup:usize, threshold:f64, save:bool) -> i32 {
+    let solver = find_solver(day).expect("day is validated by the parser");
+    if !solver.implements(part) {
+        return fail(EXIT_FAILURE, SolveError::NotImplemented(day, part));
+    }
+    let input = match load_input(Some(source), day) {
+        Ok(input) => input,
+        Err(code) => return code,
+    };
+    let mut baseline = match Baseline::load_or_default(BASELINE_FILE) {
+        Ok(baseline) => baseline,
+        Err(err) => return fail(EXIT_INPUT, err),
+    };
+
+    let stats = bench(solver, part, &input, warmup, runs);
+    let regression = baseline.compare(day, part, &stats);
+    print!("{}", format_bench(day, part, &stats, regression.as_ref()));
+
+    if save {
+        baseline.set(day, part, stats);
+        if let Err(err) = baseline.save(BASELINE_FILE) {
+            return fail(EXIT_FAILURE, err);
+        }
+        println!("saved baseline in {}", BASELINE_FILE);
+    } else if let Some(regression) = regression.filter(|regression| regression.is_regression(threshold)) {
+        println!("REGRESSION median is {:.1}% slower than the baseline, threshold {}%", regression.change, threshold);
+        return EXIT_FAILURE;
+    }
+    0
+}
+
+// Runs a program on the inputs and prints its outputs, as text with `ascii`
+// where values past 255 get a line of their own
+fn run_intcode(path:&str, inputs:Vec<i64>, ascii:bool, gdb:Option<String>) -> i32 {
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(err) => return fail(EXIT_INPUT, format!("cannot read {}: {}", path, err)),
+    };
+    let opcodes:Result<Vec<i64>, _> = text.split(',').map(|op| op.trim().parse::<i64>()).collect();
+    let mut program = match opcodes {
+        Ok(opcodes) => Program::new(opcodes, Some(inputs)),
+        Err(_) => return fail(EXIT_INPUT, format!("{} is not an Intcode program", path)),
+    };
+
+    if let Some(address) = gdb {
+        let listener = match std::net::TcpListener::bind(&address) {
+            Ok(listener) => listener,
+            Err(err) => return fail(EXIT_FAILURE, format!("cannot listen on {}: {}", address, err)),
+        };
+        eprintln!("waiting for gdb on {}", address);
+        return match GdbStub::new(program).serve_tcp(&listener) {
+            Ok(()) => 0,
+            Err(err) => fail(EXIT_FAILURE, err),
+        };
+    }
+
+    let stop = program.run_until_stop();
+    let outputs = program.take_output();
+    if ascii {
+        let mut text = String::new();
+        for value in outputs {
+            match u8::try_from(value) {
+                Ok(byte) => text.push(byte as char),
+                Err(_) => text.push_str(format!("\n{}\n", value).as_str()),
+            }
+        }
+        print!("{}", text);
+    } else if !outputs.is_empty() {
+        println!("{}", outputs.iter().map(|value| value.to_string()).collect::<Vec<String>>().join(","));
+    }
+
+    match stop {
+        StopReason::Halted => 0,
+        StopReason::NeedsInput => fail(EXIT_FAILURE, "the program wants more input"),
+        StopReason::Error(message) => fail(EXIT_FAILURE, message),
+        StopReason::Breakpoint(pc) => fail(EXIT_FAILURE, format!("stopped at breakpoint {}", pc)),
+    }
+}
+
+// Lit pixels are drawn as blocks, whether a day marks them with 1 or #
+fn render_day(day:u8, part:Part, source:Option<InputSource>) -> i32 {
+    let input = match load_input(source, day) {
+        Ok(input) => input,
+        Err(code) => return code,
+    };
+
+    match get_solution(day, part, input) {
+        Ok(Answer::Art(art)) => {
+            let picture:String = art.chars()
+                .map(|ch| match ch {
+                    '1' | '#' => '█',
+                    '\n' => '\n',
+                    _ => ' ',
+                })
+                .collect();
+            println!("{}", picture);
+            0
+        },
+        Ok(answer) => {
+            println!("{}", answer);
+            0
+        },
+        Err(err) => fail(EXIT_FAILURE, err),
+    }
 }
@@ -1,3 +1,6 @@
+#[macro_use]
+pub mod log;
+
 mod day_01;
 mod day_02;
 mod day_03;
@@ -21,45 +24,45 @@ mod day_20;
 mod day_21;
 mod day_23;
 mod day_24;
-mod int_code_computer;
+pub mod grid;
+pub mod int_code_cache;
+pub mod int_code_computer;
+pub mod int_code_fuzz;
+pub mod int_code_symbolic;
+pub mod int_code_search;
+pub mod int_code_observer;
+pub mod int_code_opcodes;
+pub mod int_code_callstack;
+pub mod int_code_memory;
+pub mod int_code_replay;
+pub mod int_code_optimizer;
+pub mod int_code_gdb;
+pub mod int_code_scheduler;
+pub mod rng;
+
+mod answers;
+mod bench;
+mod input;
+mod run_all;
+mod solver;
+
+pub use answers::*;
+pub use bench::*;
+pub use input::*;
+pub use run_all::*;
+pub use solver::*;
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Part {
     Part1,
     Part2,
 }
 
-
-
-pub fn get_solution(day:u8, part:Part, input:String) -> String {
-    match day {
-        1 => day_01::solve(input, part),
-        2 => day_02::solve(input, part),
-        3 => day_03::solve(input, part),
-        4 => day_04::solve(input, part),
-        5 => day_05::solve(input, part),
-        6 => day_06::solve(input, part),
-        7 => day_07::solve(input, part),
-        8 => day_08::solve(input, part),
-        9 => day_09::solve(input, part),
-        10 => day_10::solve(input, part),
-        11 => day_11::solve(input, part),
-        12 => day_12::solve(input, part),
-        13 => day_13::solve(input, part),
-        14 => day_14::solve(input, part),
-        15 => day_15::solve(input, part),
-        16 => day_16::solve(input, part),
-        17 => day_17::solve(input, part),
-        18 => day_18::solve(input, part),
-        19 => day_19::solve(input, part),
-        20 => day_20::solve(input, part),
-        21 => day_21::solve(input, part),
-        23 => day_23::solve(input, part),
-        24 => day_24::solve(input, part),
-        _ => panic!("..."),
+impl std::fmt::Display for Part {
+    fn fmt(&self, f:&mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Part::Part1 => f.pad("1"),
+            Part::Part2 => f.pad("2"),
+        }
     }
-
 }
-
-
-
-
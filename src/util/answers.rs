@@ -0,0 +1,294 @@
+use crate::util::*;
+use std::collections::BTreeMap;
+use std::fmt;
+
+pub const ANSWERS_FILE:&str = "answers.toml";
+
+// Expected answers per day and part, stored in a small subset of TOML:
+//
+//   [day7]
+//   part1 = 338603
+//   part2 = "text"
+//   [day8]
+//   part2 = """
+//   picture lines
+//   """
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Answers {
+    pub expected:BTreeMap<(u8, u8), Answer>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Check {
+    Match,
+    Mismatch{expected:Answer, actual:Answer},
+    // Recorded, but the run did not produce an answer
+    Failed(Status),
+    // Solved, but nothing recorded to compare with
+    Unrecorded(Answer),
+}
+
+pub fn part_number(part:Part) -> u8 {
+    match part {
+        Part::Part1 => 1,
+        Part::Part2 => 2,
+    }
+}
+
+impl Answers {
+    pub fn get(&self, day:u8, part:Part) -> Option<&Answer> {
+        self.expected.get(&(day, part_number(part)))
+    }
+
+    pub fn set(&mut self, day:u8, part:Part, answer:Answer) {
+        self.expected.insert((day, part_number(part)), answer);
+    }
+
+    pub fn days(&self) -> Vec<u8> {
+        let mut days:Vec<u8> = self.expected.keys().map(|(day, _)| *day).collect();
+        days.dedup();
+        days
+    }
+
+    pub fn load(path:&str) -> Result<Answers, String> {
+        let text = std::fs::read_to_string(path).map_err(|err| format!("{}: {}", path, err))?;
+        Answers::parse(&text).map_err(|err| format!("{}: {}", path, err))
+    }
+
+    // Like `load`, but a missing file is an empty set of answers. Any other
+    // error is kept, so that a broken file is not overwritten by recording.
+    pub fn load_or_default(path:&str) -> Result<Answers, String> {
+        match std::fs::read_to_string(path) {
+            Ok(text) => Answers::parse(&text).map_err(|err| format!("{}: {}", path, err)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Answers::default()),
+            Err(err) => Err(format!("{}: {}", path, err)),
+        }
+    }
+
+    pub fn save(&self, path:&str) -> Result<(), String> {
+        std::fs::write(path, self.to_string()).map_err(|err| format!("{}: {}", path, err))
+    }
+
+    pub fn parse(text:&str) -> Result<Answers, String> {
+        let mut answers = Answers::default();
+        let mut day = None;
+        let mut lines = text.lines().enumerate();
+
+        while let Some((line_no, line)) = lines.next() {
+            let error = |message:&str| format!("line {}: {}", line_no + 1, message);
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if let Some(table) = line.strip_prefix("[day").and_then(|rest| rest.strip_suffix(']')) {
+                day = Some(table.parse::<u8>().map_err(|_| error("bad day table"))?);
+                continue;
+            }
+
+            let (key, value) = line.split_once('=').ok_or_else(|| error("expected 'partN = answer'"))?;
+            let part = match key.trim() {
+                "part1" => Part::Part1,
+                "part2" => Part::Part2,
+                _ => return Err(error("expected part1 or part2")),
+            };
+            let day = day.ok_or_else(|| error("answer outside of a [dayN] table"))?;
+            let value = value.trim();
+
+            let answer = if value == "\"\"\"" {
+                let mut art = vec![];
+                loop {
+                    match lines.next() {
+                        Some((_, "\"\"\"")) => break,
+                        Some((_, line)) => art.push(line),
+                        None => return Err(error("unterminated multi-line string")),
+                    }
+                }
+                Answer::Art(art.join("\n"))
+            } else if let Some(text) = value.strip_prefix('"').and_then(|value| value.strip_suffix('"')) {
+                Answer::Text(unescape(text).ok_or_else(|| error("bad escape in string"))?)
+            } else {
+                Answer::Number(value.parse().map_err(|_| error("expected a number or a string"))?)
+            };
+            answers.set(day, part, answer);
+        }
+
+        Ok(answers)
+    }
+
+    pub fn check(&self, result:&RunResult) -> Option<Check> {
+        match (self.get(result.day, result.part), &result.status) {
+            (Some(expected), Status::Solved(actual)) if expected == actual => Some(Check::Match),
+            (Some(expected), Status::Solved(actual)) => Some(Check::Mismatch{expected:expected.clone(), actual:actual.clone()}),
+            (Some(_), status) => Some(Check::Failed(status.clone())),
+            (None, Status::Solved(actual)) => Some(Check::Unrecorded(actual.clone())),
+            (None, _) => None,
+        }
+    }
+
+    // Takes over every solved answer, keeping the recorded ones of parts that failed
+    pub fn record(&mut self, results:&[RunResult]) -> usize {
+        let mut changed = 0;
+        for result in results {
+            if let Status::Solved(answer) = &result.status {
+                if self.get(result.day, result.part) != Some(answer) {
+                    self.set(result.day, result.part, answer.clone());
+                    changed += 1;
+                }
+            }
+        }
+        changed
+    }
+}
+
+impl fmt::Display for Answers {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "# Expected answers, checked by `verify` and rewritten by `record`")?;
+
+        let mut last_day = None;
+        for ((day, part), answer) in self.expected.iter() {
+            if last_day != Some(*day) {
+                writeln!(f, "\n[day{}]", day)?;
+                last_day = Some(*day);
+            }
+            match answer {
+                Answer::Number(number) => writeln!(f, "part{} = {}", part, number)?,
+                Answer::Text(text) => writeln!(f, "part{} = \"{}\"", part, escape(text))?,
+                Answer::Art(art) => writeln!(f, "part{} = \"\"\"\n{}\n\"\"\"", part, art)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+fn escape(text:&str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn unescape(text:&str) -> Option<String> {
+    let mut output = String::new();
+    let mut chars = text.chars();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\\' => match chars.next()? {
+                '\\' => output.push('\\'),
+                '"' => output.push('"'),
+                'n' => output.push('\n'),
+                _ => return None,
+            },
+            _ => output.push(ch),
+        }
+    }
+    Some(output)
+}
+
+pub fn format_checks(results:&[RunResult], answers:&Answers) -> (String, usize) {
+    let mut output = String::new();
+    let mut failures = 0;
+
+    for result in results {
+        let line = match answers.check(result) {
+            Some(Check::Match) => String::from("ok"),
+            Some(Check::Mismatch{expected, actual}) => {
+                failures += 1;
+                format!("MISMATCH expected {} got {}", single_line(&expected), single_line(&actual))
+            },
+            Some(Check::Failed(status)) => {
+                failures += 1;
+                match status {
+                    Status::Panicked(message) => format!("FAILED panicked: {}", message),
+                    Status::NotImplemented => String::from("FAILED not implemented"),
+                    Status::MissingInput => String::from("FAILED no input"),
+                    Status::Solved(_) => unreachable!(),
+                }
+            },
+            Some(Check::Unrecorded(actual)) => format!("unrecorded, got {}", single_line(&actual)),
+            None => continue,
+        };
+        output.push_str(format!("day {:>2} part {}: {}\n", result.day, result.part, line).as_str());
+    }
+
+    (output, failures)
+}
+
+fn single_line(answer:&Answer) -> String {
+    match answer {
+        Answer::Art(art) => format!("<art, {} lines>", art.lines().count()),
+        answer => answer.to_string(),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope.
+    use super::*;
+    use std::time::Duration;
+
+    fn result(day:u8, part:Part, status:Status) -> RunResult {
+        RunResult{day, part, status, elapsed:Duration::from_millis(1)}
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut answers = Answers::default();
+        answers.set(7, Part::Part1, Answer::Number(338603));
+        answers.set(8, Part::Part2, Answer::Art(String::from("# #\n # ")));
+        answers.set(17, Part::Part2, Answer::Text(String::from("say \"hi\"")));
+
+        let text = answers.to_string();
+        assert!(text.contains("[day7]\npart1 = 338603\n"));
+        assert!(text.contains("part2 = \"\"\"\n# #\n # \n\"\"\"\n"));
+        assert!(text.contains("part2 = \"say \\\"hi\\\"\"\n"));
+        assert_eq!(Answers::parse(&text), Ok(answers));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(Answers::parse("part1 = 3\n").is_err());
+        assert!(Answers::parse("[day1]\npart3 = 3\n").is_err());
+        assert!(Answers::parse("[day1]\npart1 = three\n").is_err());
+        assert!(Answers::parse("[day1]\npart1 = \"\"\"\nopen\n").is_err());
+    }
+
+    #[test]
+    fn test_load_or_default() {
+        // Unique per process so that concurrent test runs do not share the file
+        let path = std::env::temp_dir().join(format!("answers_test_{}.toml", std::process::id()));
+        let path = path.to_str().unwrap();
+        let _ = std::fs::remove_file(path);
+
+        let missing = Answers::load_or_default(path);
+        std::fs::write(path, "[day7]\npart1 = seven\n").unwrap();
+        let broken = Answers::load_or_default(path);
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(missing, Ok(Answers::default()));
+        assert!(broken.is_err());
+    }
+
+    #[test]
+    fn test_check_and_record() {
+        let mut answers = Answers::default();
+        answers.set(1, Part::Part1, Answer::Number(1));
+        answers.set(1, Part::Part2, Answer::Number(2));
+        answers.set(2, Part::Part1, Answer::Number(3));
+
+        let results = vec![
+            result(1, Part::Part1, Status::Solved(Answer::Number(1))),
+            result(1, Part::Part2, Status::Solved(Answer::Number(5))),
+            result(2, Part::Part1, Status::Panicked(String::from("boom"))),
+            result(2, Part::Part2, Status::Solved(Answer::Number(4))),
+        ];
+
+        let (report, failures) = format_checks(&results, &answers);
+        assert_eq!(failures, 2);
+        assert_eq!(report, "day  1 part 1: ok\nday  1 part 2: MISMATCH expected 2 got 5\n\
+                            day  2 part 1: FAILED panicked: boom\nday  2 part 2: unrecorded, got 4\n");
+
+        assert_eq!(answers.record(&results), 2);
+        assert_eq!(answers.get(1, Part::Part2), Some(&Answer::Number(5)));
+        assert_eq!(answers.get(2, Part::Part1), Some(&Answer::Number(3)));
+        assert_eq!(answers.get(2, Part::Part2), Some(&Answer::Number(4)));
+    }
+}
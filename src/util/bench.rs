@@ -0,0 +1,244 @@
+use crate::util::*;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+pub const BASELINE_FILE:&str = "bench_baseline.txt";
+
+// Counts allocations process wide, so the count of a run includes anything
+// other threads allocate meanwhile. Allocations are only reported by a binary
+// that installs it:
+//
+//   #[global_allocator]
+//   static ALLOCATOR:CountingAllocator = CountingAllocator;
+pub struct CountingAllocator;
+
+static ALLOCATIONS:AtomicU64 = AtomicU64::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout:Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr:*mut u8, layout:Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr:*mut u8, layout:Layout, new_size:usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchStats {
+    pub runs:usize,
+    pub min:Duration,
+    pub median:Duration,
+    pub p95:Duration,
+    // Allocations per run, only known for fresh measurements with the
+    // counting allocator installed
+    pub allocations:Option<u64>,
+}
+
+impl BenchStats {
+    pub fn from_samples(samples:&[Duration], allocations:Option<u64>) -> BenchStats {
+        let mut sorted = samples.to_vec();
+        sorted.sort();
+        // Nearest rank percentile
+        let percentile = |p:usize| sorted[(sorted.len() * p).div_ceil(100).max(1) - 1];
+        BenchStats{runs:sorted.len(), min:sorted[0], median:percentile(50), p95:percentile(95), allocations}
+    }
+}
+
+// Runs the part `warmup` times unmeasured and then `runs` times measured
+pub fn bench(solver:&dyn Solver, part:Part, input:&str, warmup:usize, runs:usize) -> BenchStats {
+    for _ in 0..warmup {
+        solver.solve(String::from(input), part);
+    }
+
+    // Nothing is counted without the allocator, while any program has allocated by now
+    let counting = ALLOCATIONS.load(Ordering::Relaxed) > 0;
+    let mut samples = vec![];
+    let mut allocations = 0;
+    for _ in 0..runs.max(1) {
+        let input = String::from(input);
+        let before = ALLOCATIONS.load(Ordering::Relaxed);
+        let start = Instant::now();
+        let answer = solver.solve(input, part);
+        samples.push(start.elapsed());
+        allocations += ALLOCATIONS.load(Ordering::Relaxed) - before;
+        drop(answer);
+    }
+
+    BenchStats::from_samples(&samples, Some(allocations / samples.len() as u64).filter(|_| counting))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Regression {
+    pub baseline:Duration,
+    pub current:Duration,
+    // Relative change of the median in percent, positive when slower
+    pub change:f64,
+}
+
+impl Regression {
+    pub fn is_regression(&self, threshold:f64) -> bool {
+        self.change > threshold
+    }
+}
+
+// Measured medians per day and part, one line each:
+//
+//   # day part runs min_ns median_ns p95_ns
+//   16 1 10 151000000 152000000 160000000
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Baseline {
+    pub entries:BTreeMap<(u8, u8), BenchStats>,
+}
+
+impl Baseline {
+    pub fn get(&self, day:u8, part:Part) -> Option<&BenchStats> {
+        self.entries.get(&(day, part_number(part)))
+    }
+
+    pub fn set(&mut self, day:u8, part:Part, mut stats:BenchStats) {
+        stats.allocations = None;
+        self.entries.insert((day, part_number(part)), stats);
+    }
+
+    pub fn load(path:&str) -> Result<Baseline, String> {
+        let text = std::fs::read_to_string(path).map_err(|err| format!("{}: {}", path, err))?;
+        Baseline::parse(&text).map_err(|err| format!("{}: {}", path, err))
+    }
+
+    // A missing file is an empty baseline
+    pub fn load_or_default(path:&str) -> Result<Baseline, String> {
+        match std::path::Path::new(path).exists() {
+            true => Baseline::load(path),
+            false => Ok(Baseline::default()),
+        }
+    }
+
+    pub fn save(&self, path:&str) -> Result<(), String> {
+        std::fs::write(path, self.to_string()).map_err(|err| format!("{}: {}", path, err))
+    }
+
+    pub fn parse(text:&str) -> Result<Baseline, String> {
+        let mut baseline = Baseline::default();
+
+        for (line_no, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let fields:Option<Vec<u64>> = line.split_whitespace().map(|field| field.parse().ok()).collect();
+            let (day, part, runs, min, median, p95) = match fields.as_deref() {
+                Some(&[day, part, runs, min, median, p95]) => (day, part, runs, min, median, p95),
+                _ => return Err(format!("line {}: expected 'day part runs min_ns median_ns p95_ns'", line_no + 1)),
+            };
+            let part = match part {
+                1 => Part::Part1,
+                2 => Part::Part2,
+                _ => return Err(format!("line {}: expected part 1 or 2", line_no + 1)),
+            };
+            let day = u8::try_from(day).map_err(|_| format!("line {}: bad day", line_no + 1))?;
+
+            baseline.set(day, part, BenchStats{runs:runs as usize,
+                min:Duration::from_nanos(min),
+                median:Duration::from_nanos(median),
+                p95:Duration::from_nanos(p95),
+                allocations:None});
+        }
+
+        Ok(baseline)
+    }
+
+    pub fn compare(&self, day:u8, part:Part, current:&BenchStats) -> Option<Regression> {
+        let baseline = self.get(day, part)?.median;
+        let change = (current.median.as_secs_f64() / baseline.as_secs_f64().max(1e-9) - 1.0) * 100.0;
+        Some(Regression{baseline, current:current.median, change})
+    }
+}
+
+impl fmt::Display for Baseline {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "# day part runs min_ns median_ns p95_ns")?;
+        for ((day, part), stats) in self.entries.iter() {
+            writeln!(f, "{} {} {} {} {} {}", day, part, stats.runs,
+                     stats.min.as_nanos(), stats.median.as_nanos(), stats.p95.as_nanos())?;
+        }
+        Ok(())
+    }
+}
+
+pub fn format_bench(day:u8, part:Part, stats:&BenchStats, regression:Option<&Regression>) -> String {
+    let mut output = format!("day {} part {}: {} runs\n", day, part, stats.runs);
+    output.push_str(format!("  min    {:>12}\n", format_duration(stats.min)).as_str());
+    output.push_str(format!("  median {:>12}\n", format_duration(stats.median)).as_str());
+    output.push_str(format!("  p95    {:>12}\n", format_duration(stats.p95)).as_str());
+    if let Some(allocations) = stats.allocations {
+        output.push_str(format!("  allocations per run {}\n", allocations).as_str());
+    }
+    match regression {
+        Some(regression) => output.push_str(format!("  baseline median {}, change {:+.1}%\n",
+                                                    format_duration(regression.baseline), regression.change).as_str()),
+        None => output.push_str("  no baseline\n"),
+    }
+    output
+}
+
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope.
+    use super::*;
+
+    fn millis(values:&[u64]) -> Vec<Duration> {
+        values.iter().map(|value| Duration::from_millis(*value)).collect()
+    }
+
+    #[test]
+    fn test_stats() {
+        let stats = BenchStats::from_samples(&millis(&[5, 1, 4, 2, 3]), None);
+        assert_eq!((stats.runs, stats.min, stats.median, stats.p95),
+                   (5, Duration::from_millis(1), Duration::from_millis(3), Duration::from_millis(5)));
+
+        let samples:Vec<u64> = (1..=100).collect();
+        let stats = BenchStats::from_samples(&millis(&samples), None);
+        assert_eq!((stats.median, stats.p95), (Duration::from_millis(50), Duration::from_millis(95)));
+    }
+
+    #[test]
+    fn test_baseline_and_regression() {
+        let mut baseline = Baseline::default();
+        baseline.set(16, Part::Part1, BenchStats::from_samples(&millis(&[100, 110, 120]), Some(7)));
+        let text = baseline.to_string();
+        assert_eq!(text, "# day part runs min_ns median_ns p95_ns\n16 1 3 100000000 110000000 120000000\n");
+        assert_eq!(Baseline::parse(&text), Ok(baseline.clone()));
+        assert!(Baseline::parse("16 3 1 1 1 1\n").is_err());
+        assert!(Baseline::parse("16 1 1 1\n").is_err());
+
+        let slower = BenchStats::from_samples(&millis(&[132]), None);
+        let regression = baseline.compare(16, Part::Part1, &slower).unwrap();
+        assert!((regression.change - 20.0).abs() < 1e-6);
+        assert!(regression.is_regression(10.0));
+        assert!(!regression.is_regression(25.0));
+        assert_eq!(baseline.compare(16, Part::Part2, &slower), None);
+    }
+
+    #[test]
+    fn test_bench_solver() {
+        let input = std::fs::read_to_string("input_1.txt").unwrap();
+        let stats = bench(find_solver(1).unwrap(), Part::Part2, &input, 1, 3);
+        assert_eq!(stats.runs, 3);
+        assert!(stats.min <= stats.median && stats.median <= stats.p95);
+        // The counting allocator is only installed by the binary
+        assert_eq!(stats.allocations, None);
+    }
+}
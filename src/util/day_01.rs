@@ -1,7 +1,7 @@
 
-use super::Part;
+use super::{Answer, Part};
 
-pub fn solve(input : String, part: Part) -> String {
+pub fn solve(input : String, part: Part) -> Answer {
 
     let masses:Vec<u32> = input.lines()
         .map(|line| line.parse().unwrap())
@@ -12,7 +12,7 @@ pub fn solve(input : String, part: Part) -> String {
         Part::Part2 => sum_fuel_part2(masses)
     };
 
-    format!("{}",result)
+    Answer::from(result)
 }
 
 fn calc_fuel(mass:u32) -> u32 {
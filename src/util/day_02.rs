@@ -1,8 +1,9 @@
 
-use super::Part;
+use super::{Answer, Part};
 use crate::util::int_code_computer::*;
+use crate::util::int_code_symbolic::*;
 
-pub fn solve(input : String, part: Part) -> String {
+pub fn solve(input : String, part: Part) -> Answer {
 
     let opcodes:Vec<i64> = input.split(',')
         .map(|op| op.trim().parse().unwrap())
@@ -13,44 +14,23 @@ pub fn solve(input : String, part: Part) -> String {
         Part::Part2 => part2(opcodes)
     };
 
-    format!("{}",result)
+    Answer::from(result)
 }
 
 
-fn part1(mut opcodes:Vec<i64>) -> i64 {
-
-    *(&mut opcodes[1]) = 12;
-    *(&mut opcodes[2]) = 2;
-
-    let mut program = Program::new(opcodes, None);
-    program.run();
-    program.get_memory(0)
+fn part1(opcodes:Vec<i64>) -> i64 {
+    run_int_codes(12, 2, opcodes)
 }
 
 fn part2(opcodes:Vec<i64>) -> i64  {
     const RESULT:i64 = 19690720;
 
-    let mut i = 0;
-    let mut output = 0;
-
-    while i < 100 {
-        let mut j = 0;
-        while j < 100 {
-            let opcode_input = opcodes.clone();
-            let result = run_int_codes(i,j,opcode_input);
-            //println!("i={}, j={}, result={}", i,j,result);
-            if result == RESULT {
-                output = 100 * i + j;
-                //println!("Found the answer");
-                return output;
-            }
-
-            j +=1;
-        }
-        i+=1;
-    };
-
-    output
+    // Noun and verb are solved for symbolically instead of trying all 10000 pairs
+    let symbols = vec![Symbol::new(Source::Memory(1), 0, 99), Symbol::new(Source::Memory(2), 0, 99)];
+    match find_inputs(&opcodes, &[], &symbols, Target::Memory(0, RESULT), 100_000) {
+        Some(solution) => 100 * solution.values[0] + solution.values[1],
+        None => 0,
+    }
 }
 
 fn run_int_codes(pos1:i64, pos2:i64, mut opcodes : Vec<i64>) -> i64 {
@@ -79,6 +59,15 @@ mod tests {
     }
 
 
+    #[test]
+    fn test_part2_symbolic() {
+        let opcodes = vec![1,0,0,3,1,1,2,3,1,3,4,3,1,5,0,3,2,1,9,19,1,10,19,23,2,9,23,27,1,6,27,31,2,31,9,35,1,5,35,39,1,10,39,43,1,10,43,47,2,13,47,51,1,10,51,55,2,55,10,59,1,9,59,63,2,6,63,67,1,5,67,71,1,71,5,75,1,5,75,79,2,79,13,83,1,83,5,87,2,6,87,91,1,5,91,95,1,95,9,99,1,99,6,103,1,103,13,107,1,107,5,111,2,111,13,115,1,115,6,119,1,6,119,123,2,123,13,127,1,10,127,131,1,131,2,135,1,135,5,0,99,2,14,0,0];
+
+        let noun = part2(opcodes.clone()) / 100;
+        let verb = part2(opcodes.clone()) % 100;
+        assert_eq!(run_int_codes(noun, verb, opcodes), 19690720);
+    }
+
     #[test]
     fn test5() {
 
@@ -1,5 +1,5 @@
 use crate::util::Part::*;
-use crate::util::Part;
+use crate::util::{Answer, Part};
 
 #[derive(Debug, Clone, Copy)]
 struct Point {
@@ -77,7 +77,7 @@ impl Line {
     }
 }
 
-pub fn solve(input:String, part:Part) -> String {
+pub fn solve(input:String, part:Part) -> Answer {
 
     let lines : Vec<String> = input.lines()
         .map( |line| line.trim().to_string())
@@ -86,10 +86,12 @@ pub fn solve(input:String, part:Part) -> String {
     let first_wire = &lines[0];
     let second_wire = &lines[1];
 
-    match part {
+    let result = match part {
         Part1 => part1( first_wire, second_wire),
         Part2 => part2(first_wire, second_wire),
-    }
+    };
+
+    Answer::from(result)
 }
 
 fn dist_from_origo(p1: &Point) -> i32 {
@@ -176,7 +178,7 @@ fn create_path(path_string:&String) -> Vec<Point> {
 }
 
 
-fn part2(path1 : &String, path2 : &String) -> String {
+fn part2(path1 : &String, path2 : &String) -> i32 {
     let wire1 = create_path(path1);
     let wire2 = create_path(path2);
 
@@ -193,11 +195,11 @@ fn part2(path1 : &String, path2 : &String) -> String {
 
     //println!("{:?}", distances);
 
-    format!("{:?}", distances[0].steps)
+    distances[0].steps
 }
 
 
-fn part1(path1 : &String, path2 : &String) -> String {
+fn part1(path1 : &String, path2 : &String) -> i32 {
     let wire1 = create_path(path1);
     let wire2 = create_path(path2);
 
@@ -210,7 +212,7 @@ fn part1(path1 : &String, path2 : &String) -> String {
 
     distances.sort();
 
-    format!("{}", distances[0])
+    distances[0]
 }
 
 
@@ -249,10 +251,10 @@ mod tests {
         let path2 = String::from("L1007,D620,R853,U77,L13,U473,L253,D410,R897,U464,L862,U281,L650,D470,R87,D204,L896,U670,L864,D950,L75,D320,R901,D785,L653,D225,L857,U616,L143,U940,L664,U131,L547,D745,R636,U569,L50,U454,R288,D254,L36,U377,L609,U929,L714,U85,L939,U923,L566,D280,R243,U948,R447,D7,R908,D151,R824,D432,R34,D81,L458,U745,L420,D982,L625,U910,L729,D274,R910,U322,L984,D88,L700,D349,L932,U510,R625,U88,L252,U785,L378,D101,R299,U66,L476,U696,R236,D46,R590,U157,R461,U305,L269,D487,L676,U467,R319,D524,R75,U65,L478,U861,L238,D716,R888,D12,L184,D578,R266,D226,L656,D172,L752,U124,L831,U810,L663,U538,R417,D770,L359,U1,R12,U791,L332,U272,R574,D942,L857,U447,R310,U342,L713,D258,R590,D585,R129,D115,R832,D967,R981,D159,R864,U423,R268,U519,L52,D493,R445,D657,R885,U166,R155,D264,R51,D632,R525,D875,R617,U898,L556,D386,L143,U278,L767,D389,R821,U869,R286,D90,R289,U54,R15,D764,R46,D674,R983,U49,R959,U779,R958,D247,R483,U156,L18,U12,L178,U540,L499,U487,L544,D336,R814,U267,R145,D135,L920,D902,L933,D507,L997,U361,L577,U425,L773,D782,R117,U851,R998,U503,R902,U781,L161,U98,L653,U633,L91,U629,L138,D19,R147,D756,R364,D529,L764,U913,L118,U856,R774,D621,R151,U154,R737,D960,R86,U458,R991,D481,R560,D858,R223,D6,R931,D301,R552,D797,R284,U368,L967,D686,R940,U410,R137,D156,L6,U643,L445,D999,R888,D277,L852,U210,L777,D36,R103,D652,R120,D67,L642,D527,R913,D858,R69,D433,R864,U75,L531,U456,L664,D452,R801,U851,L824,D278,L526,U133,R200,U768,R15,U393,R982,U287,L38,D114,R86,U299,L819,D891,R379,D601,L244");
 
         let result = part1(&path1, &path2);
-        assert_eq!("489", result);
+        assert_eq!(489, result);
 
         let result2 = part2(&path1, &path2);
-        assert_eq!("93654", result2);
+        assert_eq!(93654, result2);
 
     }
 
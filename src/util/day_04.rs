@@ -1,7 +1,7 @@
 use crate::util::Part::*;
-use crate::util::Part;
+use crate::util::{Answer, Part};
 
-pub fn solve(input:String, part:Part) -> String {
+pub fn solve(input:String, part:Part) -> Answer {
 
     let lines : Vec<String> = input.split('-')
         .map(|line| line.trim().to_string())
@@ -11,8 +11,8 @@ pub fn solve(input:String, part:Part) -> String {
     let to = (&lines[1]).parse().unwrap();
 
     match part {
-        Part1 => count_valid(from, to, is_valid).to_string(),
-        Part2 => count_valid(from, to, is_valid2).to_string(),
+        Part1 => Answer::from(count_valid(from, to, is_valid)),
+        Part2 => Answer::from(count_valid(from, to, is_valid2)),
     }
 }
 
@@ -1,8 +1,8 @@
-use crate::util::Part;
+use crate::util::{Answer, Part};
 use std::collections::HashMap;
 
 
-pub fn solve(input:String, part:Part) -> String {
+pub fn solve(input:String, part:Part) -> Answer {
     let mut planets:HashMap<String, Vec<String> > = HashMap::new();
 
     // Build up system
@@ -27,8 +27,8 @@ pub fn solve(input:String, part:Part) -> String {
     }
 
     match part {
-        Part::Part1 => part1(planets).to_string(),
-        Part::Part2 => part2(planets).to_string(),
+        Part::Part1 => Answer::from(part1(planets)),
+        Part::Part2 => Answer::from(part2(planets)),
     }
 }
 
@@ -89,7 +89,7 @@ mod tests {
 
         let res = solve(inputs.to_string(), Part::Part1);
         println!("{}",res);
-        assert_eq!("42", res);
+        assert_eq!(Answer::Number(42), res);
     }
 
     #[test]
@@ -110,6 +110,6 @@ mod tests {
 
         let res = solve(inputs.to_string(), Part::Part2);
         println!("{}",res);
-        assert_eq!("4", res);
+        assert_eq!(Answer::Number(4), res);
     }
 }
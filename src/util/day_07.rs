@@ -1,8 +1,10 @@
-use crate::util::Part;
+use crate::util::{Answer, Part};
 use crate::util::int_code_computer::*;
+use crate::util::int_code_scheduler::*;
+use crate::util::int_code_search::*;
 use permute;
 
-pub fn solve(input:String, part:Part) -> String {
+pub fn solve(input:String, part:Part) -> Answer {
 
     let opcodes:Vec<i64> = input.split(',')
         .map(|op| op.trim().parse().unwrap())
@@ -13,88 +15,62 @@ pub fn solve(input:String, part:Part) -> String {
         Part::Part2 => part2(opcodes)
     };
 
-    format!("{}",result)
+    Answer::from(result)
 }
 
 fn part1(opcodes:Vec<i64>) -> i64 {
-    let mut max_value = 0;
     let initial_permutation = vec![0,1,2,3,4];
 
-    for phase_setting in permute::permute(initial_permutation) {
-        let value = run_thrusters(phase_setting, &opcodes, None);
-        max_value = std::cmp::max( value.unwrap(), max_value);
-    }
+    let result = search(permute::permute(initial_permutation), Objective::Max, default_threads(),
+                        |phase_setting| run_thrusters(phase_setting.clone(), &opcodes, None));
 
-    max_value
+    result.best.unwrap().1
 }
 
 fn part2(opcodes:Vec<i64>) -> i64 {
-    let mut max_value = 0;
     let initial_permutation = vec![5,6,7,8,9];
 
-    for phase_setting in permute::permute(initial_permutation).iter() {
-        let value = run_thrusters2(phase_setting.clone(), &opcodes);
-        max_value = std::cmp::max( value, max_value);
-        //println!("Permutation:{:?}, value={}", phase_setting, value);
-    }
+    let result = search(permute::permute(initial_permutation), Objective::Max, default_threads(),
+                        |phase_setting| Some(run_thrusters2(phase_setting.clone(), &opcodes)));
 
-    max_value
+    result.best.unwrap().1
 }
 
 
-fn run_thrusters(phase_settings:Vec<i64>, opcodes:&Vec<i64>, input:Option<i64>) -> Option<i64> {
-    let mut last_output = input.or(Some(0));
-
-    for i in 0..5 {
-        let mut program = Program::new(opcodes.clone(), Some(vec![phase_settings[i], last_output.unwrap()]));
-        program.run();
-        last_output = program.get_last_output();
-    }
+// Instructions an amplifier may run before the next one gets its turn
+const QUANTUM:u64 = 1000;
 
-    last_output
+fn run_thrusters(phase_settings:Vec<i64>, opcodes:&[i64], input:Option<i64>) -> Option<i64> {
+    run_amplifiers(&phase_settings, opcodes, input.unwrap_or(0), false)
 }
 
-fn run_thrusters2(phase_settings:Vec<i64>, opcodes:&Vec<i64>) -> i64 {
-    let mut next_input = 0;
-    let mut last_produced_output = 0;
-    let mut programs = vec![];
-    let mut is_halted = false;
-
-    // Allocate programs
-    for i in 0..5 {
-        let mut program = Program::new(opcodes.clone(), None);
-        program.add_input(phase_settings[i]);
-        programs.push(program);
-    }
-
-    while !is_halted {
-        for i in 0..5 {
-            let program = programs.get_mut(i).unwrap();
-
-            // Push input
-            program.add_input(next_input);
-
-            // Run until output is produced
-            while !program.is_halted() && program.get_output().len() == 0 {
-                program.run_instruction();
-            }
+fn run_thrusters2(phase_settings:Vec<i64>, opcodes:&[i64]) -> i64 {
+    run_amplifiers(&phase_settings, opcodes, 0, true).unwrap()
+}
 
-            // Check output if any
-            if !program.get_output().is_empty() {
-                // Fetch next input
-                next_input = program.pop_output();
-                if i == 4 {
-                    // Store last produced output by thruster E
-                    last_produced_output = next_input;
+// Chains one amplifier per phase setting, with feedback the output of the last
+// one is fed back to the first until they halt. Returns the last thruster signal.
+fn run_amplifiers(phase_settings:&[i64], opcodes:&[i64], input:i64, feedback:bool) -> Option<i64> {
+    let mut scheduler = Scheduler::new(QUANTUM);
+    let amplifiers:Vec<Pid> = phase_settings.iter()
+        .map(|phase| scheduler.spawn(Program::new(opcodes.to_vec(), Some(vec![*phase]))))
+        .collect();
+    scheduler.send(amplifiers[0], input);
+
+    let last = amplifiers[amplifiers.len() - 1];
+    let mut last_produced_output = None;
+    scheduler.run_until_idle(|scheduler, amplifier, outputs| {
+        for output in outputs {
+            if amplifier == last {
+                // Store last produced output by thruster E
+                last_produced_output = Some(output);
+                if !feedback {
+                    continue;
                 }
             }
-
-            if program.is_halted() && i == 4 {
-                is_halted = true;
-                break;
-            }
+            scheduler.send(amplifiers[(amplifier + 1) % amplifiers.len()], output);
         }
-    }
+    });
 
     last_produced_output
 }
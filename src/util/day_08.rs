@@ -1,16 +1,14 @@
-use crate::util::Part;
+use crate::util::{Answer, Part};
 
-pub fn solve(input:String, part:Part) -> String {
+pub fn solve(input:String, part:Part) -> Answer {
 
-    let result = match part {
-        Part::Part1 => part1(input.trim().to_string(), 25, 6),
-        Part::Part2 => part2(input.trim().to_string(), 25 , 6)
-    };
-
-    format!("{}",result)
+    match part {
+        Part::Part1 => Answer::from(part1(input.trim().to_string(), 25, 6)),
+        Part::Part2 => Answer::art(part2(input.trim().to_string(), 25 , 6))
+    }
 }
 
-fn part1(input:String, pic_width:usize, pic_height:usize) -> String {
+fn part1(input:String, pic_width:usize, pic_height:usize) -> u32 {
     let layers:Vec<[u32;10]>  = input.as_bytes()
         .chunks(pic_width*pic_height).map(
         |layer_str| {
@@ -28,7 +26,7 @@ fn part1(input:String, pic_width:usize, pic_height:usize) -> String {
         .min_by_key(|&item| item[0])
         .unwrap();
 
-    format!("{}",min_zero_layer[1] * min_zero_layer[2])
+    min_zero_layer[1] * min_zero_layer[2]
 }
 
 fn part2(input:String, pic_width:usize, pic_height:usize) -> String {
@@ -73,7 +71,7 @@ mod tests {
         let indata = include_str!("../../input_08.txt");
         let res = solve(indata.trim().to_string(), Part1);
         println!("res = {}", res);
-        assert_eq!(res, "2904");
+        assert_eq!(res, Answer::Number(2904));
 
     }
 
@@ -107,7 +105,7 @@ mod tests {
     #[test]
     fn test3() {
         let indata = include_str!("../../input_08.txt");
-        let res = solve(indata.trim().to_string(), Part2);
+        let res = solve(indata.trim().to_string(), Part2).to_string();
         println!("------------");
         println!("{}",res.len());
         println!("{}", res);
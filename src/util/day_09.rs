@@ -1,8 +1,8 @@
-use crate::util::Part;
+use crate::util::{Answer, Part};
 use crate::util::int_code_computer::*;
 
 
-pub fn solve(input:String, part:Part) -> String {
+pub fn solve(input:String, part:Part) -> Answer {
 
     let opcodes:Vec<i64> = input.split(',')
         .map(|op| op.trim().parse().unwrap())
@@ -13,7 +13,7 @@ pub fn solve(input:String, part:Part) -> String {
         Part::Part2 => part2(opcodes)
     };
 
-    format!("{}",result)
+    Answer::from(result)
 }
 
 fn part1(opcodes:Vec<i64>) -> i64 {
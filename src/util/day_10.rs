@@ -1,17 +1,17 @@
-use crate::util::Part;
+use crate::util::{Answer, Part};
 use std::f64::consts::PI;
 use std::collections::*;
 use std::cmp::Ordering;
 use std::cmp::Ordering::{Greater, Equal, Less};
 
-pub fn solve(input:String, part:Part) -> String {
+pub fn solve(input:String, part:Part) -> Answer {
 
     let result = match part {
         Part::Part1 => part1(create_asteroid_list(input)),
         Part::Part2 => part2(create_asteroid_list(input), 200)
     };
 
-    format!("{}",result)
+    Answer::from(result)
 }
 
 fn create_asteroid_list(input:String) -> Vec<Asteroid> {
@@ -143,7 +143,7 @@ fn part2(asteroids:Vec<Asteroid>, target:i64) -> usize {
                 // Destroy one asteroid
                 let asteroid = cnt.remove(0);
                 num_destroyed += 1;
-                println!("[{}] Destroyed {},{} at angle:{}, left:{}",num_destroyed,asteroid.x,asteroid.y, *angle,cnt.len());
+                trace!("[{}] Destroyed {},{} at angle:{}, left:{}",num_destroyed,asteroid.x,asteroid.y, *angle,cnt.len());
 
                 if num_destroyed == target {
                     return (asteroid.x * 100 + asteroid.y) as usize;
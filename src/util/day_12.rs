@@ -1,15 +1,15 @@
-use crate::util::Part;
+use crate::util::{Answer, Part};
 use regex::Regex;
 
 
-pub fn solve(input:String, part:Part) -> String {
+pub fn solve(input:String, part:Part) -> Answer {
     let planets = create_planets(input.as_str());
     let result = match part {
         Part::Part1 => part1(planets),
         Part::Part2 => part2(input.as_str())
     };
 
-    format!("{}",result)
+    Answer::from(result)
 }
 
 fn create_planets(input:&str) -> Vec<Planet> {
@@ -128,12 +128,12 @@ fn check_period(mut pos_x:[i32;4],max:u64) -> u64 {
         n += 1;
 
         if first_pos_x.eq(&pos_x) && vel_x.eq(&[0;4]) {
-            println!("Found at step {}", n);
+            debug!("Found at step {}", n);
             return n;
         }
 
         if n % 1000_000 == 0 {
-            println!("Steps = {}", n);
+            trace!("Steps = {}", n);
         }
     }
 
@@ -1,9 +1,11 @@
-use crate::util::Part;
+use crate::util::{Answer, Part};
 use crate::util::int_code_computer::*;
+use crate::util::log::Level;
 use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 
 
-pub fn solve(input:String, part:Part) -> String {
+pub fn solve(input:String, part:Part) -> Answer {
 
     let opcodes:Vec<i64> = input.split(',')
         .map(|op| op.trim().parse().unwrap())
@@ -14,26 +16,33 @@ pub fn solve(input:String, part:Part) -> String {
         Part::Part2 => part2(opcodes)
     };
 
-    format!("{}",result)
+    Answer::from(result)
 }
 
-fn part1(opcodes:Vec<i64>) -> usize {
-    let mut program = Program::new(opcodes.clone(), None);
-    let mut map:HashMap<(i64,i64),i64> = HashMap::new();
-
-    while !program.is_halted() {
-        // Run instruction
-        program.run_instruction();
-
-        if program.get_output().len() == 3 {
-            let tile_id = program.pop_output();
-            let y = program.pop_output();
-            let x = program.pop_output();
+// Builds the screen as the tiles are drawn, three outputs at a time
+#[derive(Default)]
+struct Screen {
+    pending:Vec<i64>,
+    map:HashMap<(i64,i64),i64>,
+}
 
-            map.insert((x,y), tile_id);
+impl Observer for Screen {
+    fn output_produced(&mut self, _pc:usize, value:i64) {
+        self.pending.push(value);
+        if self.pending.len() == 3 {
+            self.map.insert((self.pending[0], self.pending[1]), self.pending[2]);
+            self.pending.clear();
         }
     }
+}
 
+fn part1(opcodes:Vec<i64>) -> usize {
+    let mut program = Program::new(opcodes, None);
+    let screen = Arc::new(Mutex::new(Screen::default()));
+    program.add_observer(screen.clone());
+    program.run();
+
+    let map = &screen.lock().unwrap().map;
     map.iter().filter(|(_,&item)| item == 2).count()
 }
 
@@ -62,13 +71,13 @@ fn part2(opcodes:Vec<i64>) -> usize {
         let x = output.pop().unwrap();
 
         if x == -1 && y == 0 {
-            println!("Score = {}", tile_id);
+            debug!("Score = {}", tile_id);
             score = tile_id;
         } else if tile_id == 3 { // Horizontal tile
-            println!("Horizontal is at x:{},y:{}", x,y);
+            trace!("Horizontal is at x:{},y:{}", x,y);
             bar_x = x;
         } else if tile_id == 4 { // Ball
-            println!("Ball is at x:{},y:{}", x,y);
+            trace!("Ball is at x:{},y:{}", x,y);
             ball_x = x;
         }
 
@@ -77,21 +86,23 @@ fn part2(opcodes:Vec<i64>) -> usize {
         // Add input
         if ball_x > bar_x {
             program.add_input(1);
-            println!("=> Move right");
+            trace!("=> Move right");
         } else if ball_x == bar_x {
             program.add_input(0);
-            println!("=> Neutral");
+            trace!("=> Neutral");
         } else if ball_x < bar_x {
-            println!("=> Move left");
+            trace!("=> Move left");
             program.add_input(-1);
         }
 
-        draw(score, &map);
+        if log_enabled!(Level::Trace) {
+            draw(score, &map);
+        }
 
     }
 
     let num_blocks = map.iter().filter(|(_,&item)| item == 2).count();
-    println!("blocks = {}", num_blocks);
+    debug!("blocks = {}", num_blocks);
 
     score as usize
 }
@@ -118,7 +129,7 @@ fn draw(score:i64, map:&HashMap<(i64,i64),i64>) {
         }
         output.push('\n');
     }
-    println!("{}", output);
+    trace!("{}", output);
 
 }
 
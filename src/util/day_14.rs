@@ -1,22 +1,23 @@
-use crate::util::Part;
+use crate::util::{Answer, Part};
+use crate::util::log::Level;
 use std::collections::HashMap;
 
 
-pub fn solve(input:String, part:Part) -> String {
+pub fn solve(input:String, part:Part) -> Answer {
     let result = match part {
         Part::Part1 => part1(input.as_str()),
         Part::Part2 => part2(input.as_str())
     };
 
-    format!("{}",result)
+    Answer::from(result)
 }
 
 fn part1(input:&str) -> u64 {
-    count_or_for_one_fuel(parse_input(input))
+    parse_graph(input).ore_for_fuel(1)
 }
 
-fn part2(_input:&str) -> u64 {
-    2
+fn part2(input:&str) -> u64 {
+    max_fuel(&parse_graph(input), 1_000_000_000_000)
 }
 
 #[derive(Debug,Clone)]
@@ -57,147 +58,158 @@ fn int_div_round_up(denominator:u64, divisor:u64) -> u64 {
     denominator / divisor
 }
 
-fn get_required_multiple(req_qty:u64, multiple:u64) -> u64 {
-    if multiple == 1 {
-        return req_qty;
-    } else if multiple < req_qty {
-        // Ex. required is 9 but multiple of 7
-        let rest = req_qty % multiple;
-        if rest > 0 {
-            return 1+(req_qty / multiple);
-        } else {
-            return req_qty / multiple;
-        }
-    } else {
-        // Ex. Required is 5 but multiple is 9
-        1
+fn parse_graph(input:&str) -> ReactionGraph {
+    let graph = ReactionGraph::new(parse_input(input)).unwrap_or_else(|err| panic!("invalid reactions: {}", err));
+    if log_enabled!(Level::Trace) {
+        trace!("reaction graph\n{}", graph.to_dot());
     }
+    graph
 }
 
-fn count_or_for_one_fuel(reactions:Vec<Reaction>) -> u64 {
-    count_ore_per_fuel(&reactions, &mut HashMap::new())
+// The reactions indexed by the chemical they produce. The input is checked
+// once when the graph is built, so queries can trust it.
+#[derive(Debug)]
+struct ReactionGraph {
+    producers:HashMap<String, Reaction>,
+    // Every chemical before the chemicals it is made of, FUEL first and ORE last
+    order:Vec<String>,
 }
 
-
-fn count_or_for_n_fuel(reactions:Vec<Reaction>, total_ore:u64) -> u64 {
-    let mut surplus_map:HashMap<String, u64> =  HashMap::new();
-    let mut ore_acc = 0;
-    let mut fuel_acc:u64 = 0;
-    while ore_acc < total_ore {
-        ore_acc += count_ore_per_fuel(&reactions,  &mut surplus_map);
-        fuel_acc += 1;
-        if surplus_map.is_empty() {
-            println!("=> at fuel {} and ore {}, surplus is empty.", fuel_acc, ore_acc);
-            break;
+impl ReactionGraph {
+    // Each chemical has one reaction producing it, except ORE which has none,
+    // and the reactions may not form a cycle. Then every chemical can be made
+    // from ORE.
+    fn new(reactions:Vec<Reaction>) -> Result<ReactionGraph, String> {
+        let mut producers:HashMap<String, Reaction> = HashMap::new();
+        for reaction in reactions {
+            let material = reaction.output.material.clone();
+            if material == "ORE" {
+                return Err(String::from("ORE cannot be produced by a reaction"));
+            }
+            if producers.insert(material.clone(), reaction).is_some() {
+                return Err(format!("{} is produced by more than one reaction", material));
+            }
+        }
+        for reaction in producers.values() {
+            for comp in reaction.input.iter() {
+                if comp.material != "ORE" && !producers.contains_key(&comp.material) {
+                    return Err(format!("{} needs {}, which no reaction produces", reaction.output.material, comp.material));
+                }
+            }
         }
-    }
-
-    let fuel_mult = fuel_acc;
-    let ore_mult = ore_acc;
 
-    let mult = total_ore / ore_mult;
-    ore_acc = mult * ore_mult;
-    fuel_acc = mult * fuel_mult;
+        // Sorted so that the order, and the DOT output, does not depend on hashing
+        let mut materials:Vec<&String> = producers.keys().collect();
+        materials.sort();
 
-    while ore_acc < total_ore {
-        ore_acc += count_ore_per_fuel(&reactions,  &mut surplus_map);
-        if ore_acc <= total_ore {
-            fuel_acc += 1;
+        let mut visited:HashMap<&str, bool> = HashMap::new();
+        let mut order = vec![];
+        for material in materials {
+            visit(material, &producers, &mut visited, &mut order)?;
         }
-    }
-
-    fuel_acc
-}
+        order.reverse();
 
-fn count_ore_per_fuel(reactions:&Vec<Reaction>, surplus_map:&mut HashMap<String, u64>) -> u64 {
-    //  Build map of materials
-    let mut material_map:HashMap<String, u64> = HashMap::new();
-    let mut reaction_map:HashMap<String, &Reaction> = HashMap::new();
-    for reaction in reactions.iter() {
-        reaction_map.insert(reaction.output.material.clone(), reaction);
+        Ok(ReactionGraph{producers, order})
     }
 
-    // Get fuel
-    let mut stack:Vec<Component> = vec![];
-    let mut ore_stack:std::collections::HashSet<String> = std::collections::HashSet::new();
-
-    stack.push( Component{material:String::from("FUEL"), qty:1});
-
-    while !stack.is_empty() {
-        // pop material
-        let material = stack.pop().unwrap();
-
-        // Get reaction
-        let &reaction = reaction_map.get(&material.material).unwrap();
-
-        let mut bank_qty = surplus_map.remove(&material.material).or(Some(0)).unwrap();
-        let mut lowest_mult = 0;
-
-        if bank_qty > 0 {
-            //println!(" => Found {} rest qty for {}", bank_qty, material.material);
+    // Raw materials, in practice only ORE, needed for `qty` units of a chemical.
+    // Everything that consumes a chemical comes before it in the order, so its
+    // full need is known before its reaction runs. None for unknown chemicals.
+    fn requirements(&self, chemical:&str, qty:u64) -> Option<HashMap<String, u64>> {
+        if !self.order.iter().any(|material| material == chemical) {
+            return None;
         }
 
-        // Determine how may times we need to run this reaction
-        while bank_qty < material.qty {
-            bank_qty += reaction.output.qty;
-            lowest_mult += 1;
+        let mut needed:HashMap<&str, u64> = HashMap::new();
+        needed.insert(chemical, qty);
+        let mut raw = HashMap::new();
+        for material in self.order.iter() {
+            let qty = match needed.remove(material.as_str()) {
+                Some(qty) => qty,
+                None => continue,
+            };
+            match self.producers.get(material) {
+                Some(reaction) => {
+                    let batches = int_div_round_up(qty, reaction.output.qty);
+                    for comp in reaction.input.iter() {
+                        *needed.entry(comp.material.as_str()).or_insert(0) += batches * comp.qty;
+                    }
+                },
+                None => {
+                    raw.insert(material.clone(), qty);
+                },
+            }
         }
+        Some(raw)
+    }
 
-        // Rest qty?
-        let rest_qty = bank_qty - material.qty;
-
-        // Update surplus map
-        if rest_qty > 0 {
-            surplus_map.insert(material.material.clone(), rest_qty);
-            //println!(" => Added {} rest qty for {}", rest_qty, material.material);
-        }
+    fn ore_for_fuel(&self, fuel:u64) -> u64 {
+        let raw = self.requirements("FUEL", fuel).expect("no reaction produces FUEL");
+        raw.get("ORE").copied().unwrap_or(0)
+    }
 
-        // Could make it on rest material
-        if lowest_mult == 0 {
-            continue;
+    // Graphviz source with an edge from every input to what it makes, labelled
+    // with the quantity one reaction uses
+    fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph reactions {\n");
+        for material in self.order.iter() {
+            match self.producers.get(material) {
+                Some(reaction) => {
+                    dot.push_str(format!("    \"{}\" [label=\"{} {}\"];\n", material, reaction.output.qty, material).as_str());
+                    for comp in reaction.input.iter() {
+                        dot.push_str(format!("    \"{}\" -> \"{}\" [label=\"{}\"];\n", comp.material, material, comp.qty).as_str());
+                    }
+                },
+                None => dot.push_str(format!("    \"{}\" [shape=box];\n", material).as_str()),
+            }
         }
+        dot.push_str("}\n");
+        dot
+    }
+}
 
-        // Push new materials required
+// Depth first post-order. Reaching a chemical that is still being visited
+// means the reactions loop back on themselves.
+fn visit<'a>(material:&'a str, producers:&'a HashMap<String, Reaction>, visited:&mut HashMap<&'a str, bool>,
+             order:&mut Vec<String>) -> Result<(), String> {
+    match visited.get(material) {
+        Some(true) => return Ok(()),
+        Some(false) => return Err(format!("the reactions for {} form a cycle", material)),
+        None => {},
+    }
+    visited.insert(material, false);
+    if let Some(reaction) = producers.get(material) {
         for comp in reaction.input.iter() {
-            let total_qty = lowest_mult * comp.qty;
-
-            if !comp.material.as_str().eq("ORE") {
-                //println!("Reaction {:?}", reaction);
-                //println!("To produce {} {} I need {} {}", material.qty, material.material, total_qty ,comp.material);
-                stack.push(Component { qty: total_qty, material: comp.material.clone() });
-
-                // Add to requirement map
-                if material_map.contains_key(&comp.material) {
-                    let cnt = material_map.get_mut(&comp.material).unwrap();
-                    *cnt += total_qty;
-                    //println!("   Updated need for {} to {}", comp.material, cnt);
-                } else {
-                    material_map.insert(comp.material.clone(), total_qty);
-                    //println!("   Need for {} to {}", comp.material, total_qty);
-                }
-            } else {
-                // Ore transition
-                ore_stack.insert(material.material.clone());
-            }
+            visit(&comp.material, producers, visited, order)?;
         }
     }
+    visited.insert(material, true);
+    order.push(String::from(material));
+    Ok(())
+}
 
-    // Transition from Ore to Materials
-    let mut ore_qty = 0;
-    for material_str in ore_stack {
-        let material_qty = material_map.get(&material_str).unwrap();
-
-        //println!("I need to produce {} of {}", material_qty, material_str);
-        let &reaction = reaction_map.get(&material_str).unwrap();
-        //println!("Reaction: {:?}", reaction);
-
-        let mult = get_required_multiple(*material_qty, reaction.output.qty);
-        ore_qty += mult * reaction.input.first().unwrap().qty;
+// The most fuel that the ore is enough for. Leftovers make fuel cheaper in bulk,
+// so the ore for one fuel only gives a lower bound to search up from.
+fn max_fuel(graph:&ReactionGraph, total_ore:u64) -> u64 {
+    let mut low = total_ore / graph.ore_for_fuel(1);
+    let mut high = low.max(1) * 2;
+    while graph.ore_for_fuel(high) <= total_ore {
+        low = high;
+        high *= 2;
+    }
 
-        //println!("Required ore: {}", mult * reaction.input.first().unwrap().qty);
+    // ore_for_fuel(low) <= total_ore < ore_for_fuel(high)
+    while high - low > 1 {
+        let middle = low + (high - low) / 2;
+        if graph.ore_for_fuel(middle) <= total_ore {
+            low = middle;
+        } else {
+            high = middle;
+        }
     }
 
-    ore_qty
+    debug!("{} ORE gives {} fuel", total_ore, low);
+    low
 }
 
 
@@ -240,7 +252,7 @@ mod tests {
 5 B, 7 C => 1 BC
 4 C, 1 A => 1 CA
 2 AB, 3 BC, 4 CA => 1 FUEL";
-        let res = count_or_for_one_fuel(parse_input(input));
+        let res = parse_graph(input).ore_for_fuel(1);
         println!("{:?}",res);
         assert_eq!(165, res);
     }
@@ -255,7 +267,7 @@ mod tests {
 7 A, 1 C => 1 D
 7 A, 1 D => 1 E
 7 A, 1 E => 1 FUEL";
-        let res = count_or_for_one_fuel(parse_input(input));
+        let res = parse_graph(input).ore_for_fuel(1);
         println!("{:?}",res);
         assert_eq!(31, res);
     }
@@ -272,7 +284,7 @@ mod tests {
 7 DCFZ, 7 PSHF => 2 XJWVT
 165 ORE => 2 GPVTF
 3 DCFZ, 7 NZVS, 5 HKGWZ, 10 PSHF => 8 KHKGT";
-        let res = count_or_for_one_fuel(parse_input(input));
+        let res = parse_graph(input).ore_for_fuel(1);
         println!("{:?}",res);
         assert_eq!(13312, res);
     }
@@ -290,9 +302,9 @@ mod tests {
 165 ORE => 2 GPVTF
 3 DCFZ, 7 NZVS, 5 HKGWZ, 10 PSHF => 8 KHKGT";
         let total_ore = 1000_000_000_000;
-        let produced_fuel = count_or_for_n_fuel(parse_input(input), total_ore);
+        let produced_fuel = max_fuel(&parse_graph(input), total_ore);
         println!("{} ORE gives {} fuel", total_ore, produced_fuel);
-
+        assert_eq!(82892753, produced_fuel);
     }
 
     #[test]
@@ -310,7 +322,7 @@ mod tests {
 1 NVRVD => 8 CXFTF
 1 VJHF, 6 MNCFX => 4 RFSQX
 176 ORE => 6 VJHF";
-        let res = count_or_for_one_fuel(parse_input(input));
+        let res = parse_graph(input).ore_for_fuel(1);
         println!("{:?}",res);
         assert_eq!(180697, res);
     }
@@ -331,9 +343,9 @@ mod tests {
 1 VJHF, 6 MNCFX => 4 RFSQX
 176 ORE => 6 VJHF";
         let total_ore = 1000_000_000_000;
-        let produced_fuel = count_or_for_n_fuel(parse_input(input), total_ore);
+        let produced_fuel = max_fuel(&parse_graph(input), total_ore);
         println!("{} ORE gives {} fuel", total_ore, produced_fuel);
-
+        assert_eq!(5586022, produced_fuel);
     }
 
     #[test]
@@ -356,12 +368,12 @@ mod tests {
 121 ORE => 7 VRPVC
 7 XCVML => 6 RJRHP
 5 BHXH, 4 VRPVC => 5 LTCX";
-        let res = count_or_for_one_fuel(parse_input(input));
+        let res = parse_graph(input).ore_for_fuel(1);
         println!("{:?}",res);
         assert_eq!(2210736, res);
     }
 
-    //#[test]
+    #[test]
     fn part2_test3() {
         println!("Test");
         let input = "171 ORE => 8 CNZTR
@@ -382,11 +394,45 @@ mod tests {
 7 XCVML => 6 RJRHP
 5 BHXH, 4 VRPVC => 5 LTCX";
         let total_ore = 1000_000_000_000;
-        let produced_fuel = count_or_for_n_fuel(parse_input(input), total_ore);
+        let produced_fuel = max_fuel(&parse_graph(input), total_ore);
         println!("{} ORE gives {} fuel", total_ore, produced_fuel);
+        assert_eq!(460664, produced_fuel);
+    }
 
+    #[test]
+    fn test_graph() {
+        let input = "9 ORE => 2 A
+8 ORE => 3 B
+7 ORE => 5 C
+3 A, 4 B => 1 AB
+5 B, 7 C => 1 BC
+4 C, 1 A => 1 CA
+2 AB, 3 BC, 4 CA => 1 FUEL";
+        let graph = parse_graph(input);
+        assert_eq!(graph.order.first().unwrap(), "FUEL");
+        assert_eq!(graph.order.last().unwrap(), "ORE");
+        assert_eq!(graph.order.len(), 8);
+
+        let raw = graph.requirements("AB", 1).unwrap();
+        assert_eq!(raw.len(), 1);
+        assert_eq!(raw["ORE"], 34);
+        assert_eq!(graph.requirements("ORE", 5).unwrap()["ORE"], 5);
+        assert!(graph.requirements("XYZ", 1).is_none());
+
+        let dot = graph.to_dot();
+        assert!(dot.starts_with("digraph reactions {\n    \"FUEL\" [label=\"1 FUEL\"];\n"));
+        assert!(dot.contains("    \"AB\" -> \"FUEL\" [label=\"2\"];\n"));
+        assert!(dot.ends_with("    \"ORE\" [shape=box];\n}\n"));
     }
 
+    #[test]
+    fn test_graph_errors() {
+        let graph = |input| ReactionGraph::new(parse_input(input)).map(|_| ()).unwrap_err();
+        assert_eq!(graph("1 ORE => 1 A\n2 ORE => 1 A\n1 A => 1 FUEL"), "A is produced by more than one reaction");
+        assert_eq!(graph("1 ORE => 1 A\n1 B => 1 FUEL"), "FUEL needs B, which no reaction produces");
+        assert_eq!(graph("1 ORE, 1 B => 1 A\n1 A => 1 B\n1 A => 1 FUEL"), "the reactions for A form a cycle");
+        assert_eq!(graph("1 A => 1 ORE\n1 ORE => 1 A"), "ORE cannot be produced by a reaction");
+    }
 
     #[test]
     fn test_part1() {
@@ -448,7 +494,7 @@ mod tests {
 3 ZMNV => 9 PLBT
 7 ZMNV, 9 RVRD, 8 SFRQ => 7 HRMT
 8 RBMLP => 6 TSRNS";
-        let res = count_or_for_one_fuel(parse_input(input));
+        let res = parse_graph(input).ore_for_fuel(1);
         println!("{:?}",res);
         assert_eq!(319014, res);
     }
@@ -1,8 +1,8 @@
-use crate::util::Part;
+use crate::util::{Answer, Part};
 use crate::util::int_code_computer::*;
 use std::collections::{HashMap, VecDeque};
 
-pub fn solve(input:String, part:Part) -> String {
+pub fn solve(input:String, part:Part) -> Answer {
 
     let opcodes:Vec<i64> = input.split(',')
         .map(|op| op.trim().parse().unwrap())
@@ -13,7 +13,7 @@ pub fn solve(input:String, part:Part) -> String {
         Part::Part2 => part2(opcodes)
     };
 
-    format!("{}",result)
+    Answer::from(result)
 }
 
 #[derive(Debug, Eq, PartialEq, Hash, Clone, Copy)]
@@ -62,6 +62,7 @@ fn draw(map:&HashMap<Pos,i64>) {
     let min_y = map.keys().map(|p| p.y).min().unwrap();
     let max_y = map.keys().map(|p| p.y).max().unwrap();
 
+    let mut picture = String::new();
     let mut y = max_y;
     while y >= min_y {
         let mut x = min_x;
@@ -74,13 +75,13 @@ fn draw(map:&HashMap<Pos,i64>) {
                 3 => 'o',
                 _ => 'X',
             };
-            print!("{}",ch);
+            picture.push(ch);
             x += 1;
         }
-        println!();
+        picture.push('\n');
         y -= 1;
     }
-    println!("------------------------");
+    debug!("{}------------------------", picture);
 }
 
 #[derive(Debug, Eq, PartialEq, Hash, Clone)]
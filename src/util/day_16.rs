@@ -1,12 +1,12 @@
-use crate::util::Part;
+use crate::util::{Answer, Part};
 
-pub fn solve(input:String, part:Part) -> String {
+pub fn solve(input:String, part:Part) -> Answer {
     let result = match part {
         Part::Part1 => part1(input.as_str()),
         Part::Part2 => part2(input.as_str())
     };
 
-    format!("{}",result)
+    Answer::Text(result)
 }
 
 fn part1(input:&str) -> String {
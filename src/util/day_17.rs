@@ -1,8 +1,8 @@
-use crate::util::Part;
+use crate::util::{Answer, Part};
 use crate::util::int_code_computer::*;
 use std::collections::{HashMap, HashSet};
 
-pub fn solve(input:String, part:Part) -> String {
+pub fn solve(input:String, part:Part) -> Answer {
     let opcodes:Vec<i64> = input.split(',')
         .map(|op| op.trim().parse().unwrap())
         .collect();
@@ -12,7 +12,7 @@ pub fn solve(input:String, part:Part) -> String {
         Part::Part2 => part2(opcodes)
     };
 
-    format!("{}",result)
+    Answer::from(result)
 }
 
 
@@ -25,7 +25,7 @@ fn build_map(opcodes:Vec<i64>) -> HashMap<(i32,i32),i64> {
 
     let output = program.get_output();
     let s:String = output.iter().map(|item| std::char::from_u32( *item as u32 ).unwrap() ).collect();
-    println!("{}",s);
+    debug!("{}",s);
 
     let map :HashMap<(i32,i32),i64> = output.split( |i| *i == 10).enumerate()
         .map(|(y, row)| {
@@ -190,7 +190,7 @@ fn part2(opcodes:Vec<i64>) -> i32 {
         }
     });
 
-    println!("res={}",res);
+    debug!("res={}",res);
     2
 }
 
@@ -219,7 +219,7 @@ fn find_common_substrings(s:&str) -> HashSet<&str> {
 fn find_valid_permutations(s:&str) -> Vec<&str> {
     let sub_strings:Vec<&str> = find_common_substrings(s).iter().map(|s| *s).collect();
     let mut solutions = vec![];
-    println!("Found sub-strings:{:?}",sub_strings);
+    debug!("Found sub-strings:{:?}",sub_strings);
 
     for n in 0..sub_strings.len() {
         let word = sub_strings[n];
@@ -1,37 +1,37 @@
-use crate::util::Part;
-use std::collections::{HashMap, HashSet, BinaryHeap};
-use std::fmt::Debug;
+use crate::util::{Answer, Part};
+use crate::util::grid::{Pos, find_all, parse_grid};
+use std::collections::{HashMap, BinaryHeap, VecDeque};
 use std::cmp::{Reverse};
 
-pub fn solve(input:String, part:Part) -> String {
+pub fn solve(input:String, part:Part) -> Answer {
     let result = match part {
         Part::Part1 => part1(input.as_str()),
         Part::Part2 => part2(input.as_str())
     };
 
-    format!("{}",result)
-}
-
-#[derive(Eq,Hash,PartialEq,Debug,Copy, Clone,Ord, PartialOrd)]
-struct Pos {
-    x:u32,
-    y:u32,
+    Answer::from(result)
 }
 
+#[cfg(test)]
 #[derive(Eq,Hash,PartialEq,Debug,Ord, PartialOrd)]
 struct Node {
-    ch:char,
-    pos:Pos,
+    robots:Vec<Pos>,
+    // The robot walking to its next key. Robots never block each other, so any
+    // order of moves can be rearranged into whole walks from key to key, and
+    // only when a new key is picked up may another robot start.
+    active:Option<usize>,
     keys:Vec<char>,
 }
 
+#[cfg(test)]
 impl Node {
     fn make_copy(&self) -> Node {
-        Node{ch:self.ch, pos:self.pos, keys:self.keys.clone()}
+        Node{robots:self.robots.clone(), active:self.active, keys:self.keys.clone()}
     }
 }
 
 
+#[cfg(test)]
 #[derive(Ord, PartialOrd, Eq, PartialEq)]
 struct QueueElement {
     // This struct will by default be sorted by shortest distance and collected keys
@@ -40,34 +40,17 @@ struct QueueElement {
 }
 
 
-fn parse_input(input:&str) -> HashMap<Pos,char> {
-    let num_lines = input.lines().count() as u32;
-    let keys: Vec<char> = input.chars().filter(|ch| ch.is_ascii_lowercase()).collect();
-    let mut key_set = HashSet::new();
-    for x in keys.iter() {
-        key_set.insert(*x);
-    }
-
-    let mut map: HashMap<Pos, char> = HashMap::new();
-    input.lines().enumerate().for_each(|(line_no, line)| {
-        line.chars().enumerate().for_each(|(x_pos, ch)| {
-            map.insert(Pos { x: x_pos as u32, y: num_lines - line_no as u32 - 1 }, ch);
-        })
-    });
-
-    map
-}
-
-
+// The search over single cells, kept to check the key graph search against
+#[cfg(test)]
 fn dijkstras(map:HashMap<Pos,char>) -> i32 {
-    // Find start pos
-    let start_pos = *map.iter().find(|&(_,v)| *v == '@').unwrap().0;
+    // Find start positions, one per robot
+    let start_pos = find_all(&map, '@');
     let num_keys = map.iter().filter(|&(_,v)| v.is_ascii_lowercase()).count();
 
     let mut iterations = 0;
 
     // Add first state
-    let start_node = Node{ch:'@',pos:start_pos, keys:Vec::new()};
+    let start_node = Node{robots:start_pos, active:None, keys:Vec::new()};
 
     let mut queue = BinaryHeap::new();
     let mut distances = HashMap::new();
@@ -79,43 +62,51 @@ fn dijkstras(map:HashMap<Pos,char>) -> i32 {
 
         // Pop first item
         let (elem,node)= queue.pop().unwrap().0;
-        let (x,y) = (node.pos.x, node.pos.y);
         let dist = elem.distance;
 
         iterations += 1;
 
         // Check if we reach exit criteria
         if node.keys.len() == num_keys {
-            println!("Iterations = {}",iterations);
+            debug!("Iterations = {}",iterations);
             return dist;
         }
 
         // Generate adjacent nodes
-        let adjacent_pos = vec![Pos{x:x+1,y:y},Pos{x:x-1,y:y},Pos{x:x,y:y-1},Pos{x:x,y:y+1}];
+        let movable = match node.active {
+            Some(robot) => vec![robot],
+            None => (0..node.robots.len()).collect(),
+        };
         let mut next_nodes = vec![];
         let next_dist = dist + 1;
-        for neighbor_pos in adjacent_pos {
-            let item = *map.get(&neighbor_pos).unwrap();
-            if item == '.' || item == '@' || (item.is_ascii_uppercase() && node.keys.contains(&item.to_ascii_lowercase())) {
-                // Open
-                next_nodes.push(Node{ch:item, pos: neighbor_pos, keys:node.keys.clone()});
-            } else if item == '#' {
-                // Wall
-                continue;
-            } else if item.is_ascii_lowercase() {
-                // Key
-                let mut next_keys = node.keys.clone();
-                if !next_keys.contains(&item) {
-                    next_keys.push(item);
-                    next_keys.sort();
+        for robot in movable {
+            for neighbor_pos in node.robots[robot].neighbours() {
+                let item = *map.get(&neighbor_pos).unwrap();
+                let mut next_robots = node.robots.clone();
+                next_robots[robot] = neighbor_pos;
+                if item == '.' || item == '@' || (item.is_ascii_uppercase() && node.keys.contains(&item.to_ascii_lowercase())) {
+                    // Open
+                    next_nodes.push(Node{robots:next_robots, active:Some(robot), keys:node.keys.clone()});
+                } else if item == '#' {
+                    // Wall
+                    continue;
+                } else if item.is_ascii_lowercase() {
+                    // Key, a new one lets any robot move next
+                    let mut next_keys = node.keys.clone();
+                    let mut active = Some(robot);
+                    if !next_keys.contains(&item) {
+                        next_keys.push(item);
+                        next_keys.sort();
+                        active = None;
+                    }
+                    next_nodes.push(Node{robots:next_robots, active, keys:next_keys});
+                }  else if item.is_ascii_uppercase() {
+                    // continue, locked door
+                    continue;
+                }
+                else {
+                    panic!("Item {}",item);
                 }
-                next_nodes.push( Node{ch:item,pos: neighbor_pos,keys:next_keys});
-            }  else if item.is_ascii_uppercase() {
-                // continue, locked door
-                continue;
-            }
-            else {
-                panic!("Item {}",item);
             }
         }
 
@@ -133,13 +124,151 @@ fn dijkstras(map:HashMap<Pos,char>) -> i32 {
     panic!("No solution!")
 }
 
+// Keys are numbered by letter, `a` is 0, and a set of keys is a bitmask
+fn key_bit(key:char) -> u32 {
+    1 << (key as u32 - 'a' as u32)
+}
+
+// The shortest walk from a start or key to another key, with the doors on
+// the way as the mask of the keys that open them
+#[derive(Debug, Clone, PartialEq)]
+struct Edge {
+    key:usize,
+    distance:i32,
+    doors:u32,
+}
+
+// The vault reduced to its keys. Node 0 to 25 are the keys `a` to `z` and
+// the robot starts follow from 26 on.
+#[derive(Debug)]
+struct KeyGraph {
+    edges:Vec<Vec<Edge>>,
+    robots:usize,
+    all_keys:u32,
+}
+
+impl KeyGraph {
+    // A key may be reached by several walks when the vault has loops, and a
+    // longer walk counts when it avoids some door of the shorter ones
+    fn new(map:&HashMap<Pos,char>) -> KeyGraph {
+        let start_pos = find_all(map, '@');
+        let mut sources:Vec<(usize, Pos)> = vec![];
+        let mut all_keys = 0;
+        for (pos, item) in map.iter() {
+            if item.is_ascii_lowercase() {
+                sources.push((*item as usize - 'a' as usize, *pos));
+                all_keys |= key_bit(*item);
+            }
+        }
+        for (robot, pos) in start_pos.iter().enumerate() {
+            sources.push((26 + robot, *pos));
+        }
+
+        let mut edges = vec![vec![]; 26 + start_pos.len()];
+        for (node, pos) in sources {
+            edges[node] = walk_to_keys(map, pos);
+        }
+        KeyGraph{edges, robots:start_pos.len(), all_keys}
+    }
+}
+
+// Breadth first from one place over the position and the doors passed so far,
+// walking through doors and keys as if they were open. A walk is dropped when an
+// earlier one, so no longer, reached the same place through a subset of its doors.
+fn walk_to_keys(map:&HashMap<Pos,char>, start:Pos) -> Vec<Edge> {
+    let mut edges = vec![];
+    let mut visited:HashMap<Pos, Vec<u32>> = HashMap::new();
+    let mut queue = VecDeque::new();
+    visited.insert(start, vec![0]);
+    queue.push_back((start, 0, 0));
+
+    while let Some((pos, distance, doors)) = queue.pop_front() {
+        for next_pos in pos.neighbours() {
+            let item = match map.get(&next_pos) {
+                Some('#') | None => continue,
+                Some(item) => *item,
+            };
+            let seen = visited.entry(next_pos).or_default();
+            if seen.iter().any(|seen_doors| seen_doors & !doors == 0) {
+                continue;
+            }
+            seen.push(doors);
+            let mut next_doors = doors;
+            if item.is_ascii_uppercase() {
+                next_doors |= key_bit(item.to_ascii_lowercase());
+            } else if item.is_ascii_lowercase() {
+                edges.push(Edge{key:item as usize - 'a' as usize, distance:distance + 1, doors});
+            }
+            queue.push_back((next_pos, distance + 1, next_doors));
+        }
+    }
+    edges
+}
+
+// Dijkstra over the node each robot stands on and the keys collected so far.
+// Every step walks one robot to a key it does not have yet.
+fn collect_keys(graph:&KeyGraph) -> i32 {
+    let start:Vec<usize> = (26..26 + graph.robots).collect();
+    let mut distances:HashMap<(Vec<usize>, u32), i32> = HashMap::new();
+    let mut queue = BinaryHeap::new();
+    distances.insert((start.clone(), 0), 0);
+    queue.push(Reverse((0, start, 0)));
+
+    while let Some(Reverse((dist, robots, keys))) = queue.pop() {
+        if keys == graph.all_keys {
+            return dist;
+        }
+        if distances.get(&(robots.clone(), keys)).is_some_and(|best| *best < dist) {
+            // Already reached at a shorter distance
+            continue;
+        }
+
+        for (robot, node) in robots.iter().enumerate() {
+            for edge in graph.edges[*node].iter() {
+                let key = 1 << edge.key;
+                if keys & key != 0 || edge.doors & !keys != 0 {
+                    continue;
+                }
+                let mut next_robots = robots.clone();
+                next_robots[robot] = edge.key;
+                let next_state = (next_robots, keys | key);
+                let next_dist = dist + edge.distance;
+                if distances.get(&next_state).is_none_or(|best| next_dist < *best) {
+                    distances.insert(next_state.clone(), next_dist);
+                    queue.push(Reverse((next_dist, next_state.0, next_state.1)));
+                }
+            }
+        }
+    }
+
+    panic!("No solution!")
+}
+
 fn part1(input:&str) -> i32 {
-    let map = parse_input(input);
-    dijkstras(map)
+    let map = parse_grid(input);
+    collect_keys(&KeyGraph::new(&map))
+}
+
+fn part2(input:&str) -> i32 {
+    let mut map = parse_grid(input);
+    split_vault(&mut map);
+    collect_keys(&KeyGraph::new(&map))
 }
 
-fn part2(_input:&str) -> i32 {
-    2
+// Walls off the cross around the entrance and puts a robot in each of the
+// four corners. Maps that are already split are left alone.
+fn split_vault(map:&mut HashMap<Pos,char>) {
+    let start_pos = find_all(map, '@');
+    if start_pos.len() != 1 {
+        return;
+    }
+    let center = start_pos[0];
+    for dy in -1..=1 {
+        for dx in -1..=1 {
+            let item = if dx != 0 && dy != 0 { '@' } else { '#' };
+            map.insert(Pos::new(center.x + dx, center.y + dy), item);
+        }
+    }
 }
 
 
@@ -197,4 +326,143 @@ mod tests {
 
     }
 
+    #[test]
+    fn test_key_graph() {
+        let input =
+            "#########
+#b.A.@.a#
+#########";
+
+        let graph = KeyGraph::new(&parse_grid(input));
+        assert_eq!(graph.robots, 1);
+        assert_eq!(graph.all_keys, 0b11);
+        assert_eq!(graph.edges[26], vec![Edge{key:0, distance:2, doors:0}, Edge{key:1, distance:4, doors:0b1}]);
+        assert_eq!(graph.edges[1], vec![Edge{key:0, distance:6, doors:0b1}]);
+    }
+
+    #[test]
+    fn test_same_as_cell_search() {
+        let examples = [
+            ("#######
+#@.A.a#
+#.###.#
+#.....#
+#######", 8),
+            ("########################
+#f.D.E.e.C.b.A.@.a.B.c.#
+######################.#
+#d.....................#
+########################", 86),
+            ("########################
+#...............b.C.D.f#
+#.######################
+#.....@.a.B.c.d.A.e.F.g#
+########################", 132),
+            ("#################
+#i.G..c...e..H.p#
+########.########
+#j.A..b...f..D.o#
+########@########
+#k.E..a...g..B.n#
+########.########
+#l.F..d...h..C.m#
+#################", 136),
+            ("########################
+#@..............ac.GI.b#
+###d#e#f################
+###A#B#C################
+###g#h#i################
+########################", 81),
+            ("#######
+#a.#Cd#
+##@#@##
+#######
+##@#@##
+#cB#Ab#
+#######", 8),
+            ("###############
+#d.ABC.#.....a#
+######@#@######
+###############
+######@#@######
+#b.....#.....c#
+###############", 24),
+            ("#############
+#DcBa.#.GhKl#
+#.###@#@#I###
+#e#d#####j#k#
+###C#@#@###J#
+#fEbA.#.FgHi#
+#############", 32),
+            ("#############
+#g#f.D#..h#l#
+#F###e#E###.#
+#dCba@#@BcIJ#
+#############
+#nK.L@#@G...#
+#M###N#H###.#
+#o#m..#i#jk.#
+#############", 72),
+        ];
+
+        for (input, steps) in examples.iter() {
+            let map = parse_grid(input);
+            assert_eq!(collect_keys(&KeyGraph::new(&map)), *steps);
+            assert_eq!(dijkstras(map), *steps);
+        }
+    }
+
+    #[test]
+    fn test_split_vault() {
+        let input =
+            "#######
+#a.#Cd#
+##...##
+##.@.##
+##...##
+#cB#Ab#
+#######";
+
+        let mut map = parse_grid(input);
+        split_vault(&mut map);
+        assert_eq!(find_all(&map, '@'), vec![Pos::new(2, 2), Pos::new(4, 2), Pos::new(2, 4), Pos::new(4, 4)]);
+        assert_eq!(map[&Pos::new(3, 3)], '#');
+        assert_eq!(part2(input), 8);
+    }
+
+    #[test]
+    fn test_part2() {
+        let input =
+            "###############
+#d.ABC.#.....a#
+######@#@######
+###############
+######@#@######
+#b.....#.....c#
+###############";
+        assert_eq!(part2(input), 24);
+
+        let input =
+            "#############
+#DcBa.#.GhKl#
+#.###@#@#I###
+#e#d#####j#k#
+###C#@#@###J#
+#fEbA.#.FgHi#
+#############";
+        assert_eq!(part2(input), 32);
+
+        let input =
+            "#############
+#g#f.D#..h#l#
+#F###e#E###.#
+#dCba@#@BcIJ#
+#############
+#nK.L@#@G...#
+#M###N#H###.#
+#o#m..#i#jk.#
+#############";
+        assert_eq!(part2(input), 72);
+    }
+
 }
\ No newline at end of file
@@ -1,7 +1,9 @@
-use crate::util::Part;
+use crate::util::{Answer, Part};
+use crate::util::int_code_cache::*;
 use crate::util::int_code_computer::*;
+use crate::util::int_code_search::*;
 
-pub fn solve(input:String, part:Part) -> String {
+pub fn solve(input:String, part:Part) -> Answer {
     let opcodes:Vec<i64> = input.split(',')
         .map(|op| op.trim().parse().unwrap())
         .collect();
@@ -11,38 +13,38 @@ pub fn solve(input:String, part:Part) -> String {
         Part::Part2 => part2(opcodes,100)
     };
 
-    format!("{}",result)
+    Answer::from(result)
 }
 
 
 
 fn part1(opcodes:Vec<i64>) -> i64 {
-    let mut sum = 0;
-    for x in 0..50 {
-        for y in 0..50 {
-            let mut program = Program::new(opcodes.clone(), None);
-            program.add_input(x);
-            program.add_input(y);
-            let flag= *program.run_until_output(1).get(0).unwrap();
-            sum += flag;
-        }
-    }
+    let template = Program::new(opcodes, None);
+    let points = (0..50).flat_map(|x| (0..50).map(move |y| (x,y)));
+
+    let result = search_program(&template, points, Objective::Sum, default_threads(), |program, (x,y)| {
+        program.add_input(*x);
+        program.add_input(*y);
+        Some(program.run_until_output(1)[0])
+    });
 
-    sum
+    result.sum
 }
 
 fn part2(opcodes:Vec<i64>,size:i64) -> i64 {
+    // The corner walk probes the same points again and again
+    let mut drone = CachedProgram::new(opcodes);
 
     let mut y0 = 0;
     let mut x0 = size;
-    while !check_point(opcodes.clone(), x0, y0) {
+    while !check_point(&mut drone, x0, y0) {
         y0 += 1;
     }
 
     // Found upper right corner
-    while check_square(opcodes.clone(), x0 - (size - 1), y0, size) != 4 {
+    while check_square(&mut drone, x0 - (size - 1), y0, size) != 4 {
         x0 += 1;
-        while !check_point(opcodes.clone(), x0, y0) {
+        while !check_point(&mut drone, x0, y0) {
             y0 += 1;
         }
     }
@@ -51,7 +53,7 @@ fn part2(opcodes:Vec<i64>,size:i64) -> i64 {
 }
 
 
-fn check_square(opcodes:Vec<i64>, x0:i64,y0:i64,len:i64) -> i64 {
+fn check_square(drone:&mut CachedProgram, x0:i64,y0:i64,len:i64) -> i64 {
     // Returns matches
     let x1 = x0 + len - 1;
     let y1 = y0 + len - 1;
@@ -59,7 +61,7 @@ fn check_square(opcodes:Vec<i64>, x0:i64,y0:i64,len:i64) -> i64 {
     let v = vec![(x0,y0),(x1,y0),(x0,y1),(x1,y1)];
     let mut s = 0;
     for (x,y) in v.iter() {
-        if check_point(opcodes.clone(), *x, *y) {
+        if check_point(drone, *x, *y) {
             s += 1;
         } else {
             break;
@@ -68,9 +70,8 @@ fn check_square(opcodes:Vec<i64>, x0:i64,y0:i64,len:i64) -> i64 {
     s
 }
 
-fn check_point(opcodes:Vec<i64>, x:i64, y:i64) -> bool {
-    let mut program = Program::new(opcodes, Some(vec![x,y]));
-    let flag= *program.run_until_output(1).get(0).unwrap();
+fn check_point(drone:&mut CachedProgram, x:i64, y:i64) -> bool {
+    let flag = drone.run(&[x,y]).unwrap()[0];
     flag == 1
 }
 
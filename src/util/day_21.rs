@@ -1,7 +1,7 @@
-use crate::util::Part;
+use crate::util::{Answer, Part};
 use crate::util::int_code_computer::*;
 
-pub fn solve(input:String, part:Part) -> String {
+pub fn solve(input:String, part:Part) -> Answer {
     let opcodes:Vec<i64> = input.split(',')
         .map(|op| op.trim().parse().unwrap())
         .collect();
@@ -11,7 +11,7 @@ pub fn solve(input:String, part:Part) -> String {
         Part::Part2 => part2(opcodes)
     };
 
-    format!("{}",result)
+    Answer::from(result)
 }
 
 fn part1(_opcodes:Vec<i64>) -> i32 {
@@ -32,7 +32,7 @@ fn run_program(input:&str, opcodes:Vec<i64>) {
         while !program.get_output().is_empty() {
             let out = program.pop_output();
             if out > 255 {
-                println!("result={}", out);
+                info!("result={}", out);
             } else {
                 tmp.push(out as u32);
             }
@@ -41,7 +41,7 @@ fn run_program(input:&str, opcodes:Vec<i64>) {
     }
 
     let out_str:String = tmp.iter().map(|n| std::char::from_u32(*n).unwrap()).collect();
-    println!("{}",out_str);
+    debug!("{}",out_str);
 }
 
 fn part2(_opcodes:Vec<i64>) -> i32 {
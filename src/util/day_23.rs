@@ -1,7 +1,8 @@
-use crate::util::Part;
+use crate::util::{Answer, Part};
 use crate::util::int_code_computer::*;
+use crate::util::int_code_scheduler::*;
 
-pub fn solve(input:String, part:Part) -> String {
+pub fn solve(input:String, part:Part) -> Answer {
     let opcodes:Vec<i64> = input.split(',')
         .map(|op| op.trim().parse().unwrap())
         .collect();
@@ -11,82 +12,60 @@ pub fn solve(input:String, part:Part) -> String {
         Part::Part2 => part2(opcodes)
     };
 
-    format!("{}",result)
+    Answer::from(result)
 }
 
+// Instructions a NIC may run before the next one gets its turn
+const QUANTUM:u64 = 1000;
+
 struct Network {
-    nics:Vec<Program>,
+    scheduler:Scheduler,
+    // Output of each NIC not yet forming a complete packet
+    pending:Vec<Vec<i64>>,
     blocked:bool,
     last_nat:Option<(i64,i64)>,
 }
 
 impl Network {
     fn new(opcodes:Vec<i64>) -> Network {
-        let mut nics:Vec<Program>= vec![];
+        let mut scheduler = Scheduler::new(QUANTUM);
         for i in 0..50 {
-            // Allocate NIC
-            let mut nic = Program::new(opcodes.clone(), Some(vec![i as i64]));
-
-            // Boot NIC
-            while !nic.is_blocked() {
-                nic.run_instruction();
-            }
-
-            nics.push(nic);
+            // Allocate NIC, it boots until it asks for its first packet
+            scheduler.spawn(Program::new(opcodes.clone(), Some(vec![i as i64])));
         }
 
-        Network{blocked:false,nics:nics, last_nat:None}
+        Network{scheduler, pending:vec![vec![]; 50], blocked:false, last_nat:None}
     }
 
     fn run_iteration(&mut self) {
-        // set blocked as true initially
-        self.blocked = true;
-
-        // Produce output
-        for i in 0..50 {
-            let nic = self.nics.get_mut(i).unwrap();
-            // Push input if missing
-            if nic.is_blocked() {
-                nic.add_input(-1);
-            }
+        let Network{scheduler, pending, blocked, last_nat} = self;
 
-            // Run until blocked
-            while !nic.is_blocked() {
-                nic.run_instruction();
-            }
-
-            // Did we produce any output?
-            if !nic.get_output().is_empty() {
-                let mut buffer = vec![];
-
-                // Drain output of NIC
-                while !nic.get_output().is_empty() {
-                    buffer.insert(0, nic.pop_output());
-                }
+        // NICs waiting for a packet get -1
+        for nic in scheduler.blocked() {
+            scheduler.send(nic, -1);
+        }
 
-                // Process messages as chunks of 3
-                for message in buffer.chunks(3) {
-                    let dest = message[0];
-
-                    let dest_nic_opt = self.nics.get_mut(dest as usize);
-
-                    if dest_nic_opt.is_some() {
-                        let dest_nic = dest_nic_opt.unwrap();
-                        dest_nic.add_input(message[1]);
-                        dest_nic.add_input(message[2]);
-                        //println!("Nic {} sent to other NIC {} produced {},{}", i, message[0], message[1], message[2]);
-                    } else {
-                        //println!("==> Nic {} sent to other address {} produced {},{}", i, message[0], message[1], message[2]);
-                        self.last_nat = Some((message[1],message[2]));
-                    }
+        // Run until every NIC waits for input again, delivering packets on the way
+        *blocked = true;
+        scheduler.run_until_idle(|scheduler, nic, outputs| {
+            pending[nic].extend(outputs);
+
+            // Process messages as chunks of 3
+            while pending[nic].len() >= 3 {
+                let message:Vec<i64> = pending[nic].drain(..3).collect();
+                let dest = message[0] as usize;
+
+                if dest < scheduler.len() {
+                    scheduler.send(dest, message[1]);
+                    scheduler.send(dest, message[2]);
+                } else {
+                    *last_nat = Some((message[1],message[2]));
                 }
 
                 // Network is not blocked
-                self.blocked = false;
-            } else {
-                //println!("Nic {} did not produce output this round, is_blocked:{}",i, nic.is_blocked());
+                *blocked = false;
             }
-        }
+        });
     }
 }
 
@@ -108,8 +87,8 @@ fn part2(opcodes:Vec<i64>) -> i64 {
 
         if network.last_nat.is_some() && network.blocked {
             let (x,y) = network.last_nat.unwrap();
-            network.nics.get_mut(0).unwrap().add_input(x);
-            network.nics.get_mut(0).unwrap().add_input(y);
+            network.scheduler.send(0, x);
+            network.scheduler.send(0, y);
 
             if last_y.is_some() && y == last_y.unwrap() {
                 return y;
@@ -1,14 +1,14 @@
-use crate::util::Part;
+use crate::util::{Answer, Part};
 use std::collections::{HashSet, HashMap};
 
-pub fn solve(input:String, part:Part) -> String {
+pub fn solve(input:String, part:Part) -> Answer {
 
     let result = match part {
         Part::Part1 => part1(input),
         Part::Part2 => part2(input,200)
     };
 
-    format!("{}",result)
+    Answer::from(result)
 }
 
 
@@ -0,0 +1,122 @@
+use std::collections::HashMap;
+use std::ops::Add;
+
+// A position on a character map, x grows to the right and y downwards
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Pos {
+    pub x:i32,
+    pub y:i32,
+}
+
+impl Pos {
+    pub fn new(x:i32, y:i32) -> Pos {
+        Pos{x, y}
+    }
+
+    pub fn step(&self, direction:Direction) -> Pos {
+        *self + direction.delta()
+    }
+
+    // Up, down, left and right of this position
+    pub fn neighbours(&self) -> [Pos;4] {
+        Direction::ALL.map(|direction| self.step(direction))
+    }
+
+    pub fn manhattan(&self, other:&Pos) -> i32 {
+        (self.x - other.x).abs() + (self.y - other.y).abs()
+    }
+}
+
+impl Add for Pos {
+    type Output = Pos;
+
+    fn add(self, other:Pos) -> Pos {
+        Pos{x:self.x + other.x, y:self.y + other.y}
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Direction {
+    pub const ALL:[Direction;4] = [Direction::Up, Direction::Down, Direction::Left, Direction::Right];
+
+    pub fn delta(&self) -> Pos {
+        match self {
+            Direction::Up => Pos{x:0, y:-1},
+            Direction::Down => Pos{x:0, y:1},
+            Direction::Left => Pos{x:-1, y:0},
+            Direction::Right => Pos{x:1, y:0},
+        }
+    }
+
+    pub fn turn_left(&self) -> Direction {
+        match self {
+            Direction::Up => Direction::Left,
+            Direction::Left => Direction::Down,
+            Direction::Down => Direction::Right,
+            Direction::Right => Direction::Up,
+        }
+    }
+
+    pub fn turn_right(&self) -> Direction {
+        self.turn_left().reverse()
+    }
+
+    pub fn reverse(&self) -> Direction {
+        match self {
+            Direction::Up => Direction::Down,
+            Direction::Down => Direction::Up,
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left,
+        }
+    }
+}
+
+// Reads a character map, the first line is y = 0
+pub fn parse_grid(input:&str) -> HashMap<Pos, char> {
+    input.lines().enumerate()
+        .flat_map(|(y, line)| line.chars().enumerate().map(move |(x, ch)| (Pos::new(x as i32, y as i32), ch)))
+        .collect()
+}
+
+pub fn find_all(grid:&HashMap<Pos, char>, wanted:char) -> Vec<Pos> {
+    let mut found:Vec<Pos> = grid.iter().filter(|(_, ch)| **ch == wanted).map(|(pos, _)| *pos).collect();
+    found.sort_by_key(|pos| (pos.y, pos.x));
+    found
+}
+
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope.
+    use super::*;
+
+    #[test]
+    fn test_pos() {
+        let pos = Pos::new(2, 3);
+        assert_eq!(pos.step(Direction::Up), Pos::new(2, 2));
+        assert_eq!(pos.neighbours(), [Pos::new(2, 2), Pos::new(2, 4), Pos::new(1, 3), Pos::new(3, 3)]);
+        assert_eq!(pos.manhattan(&Pos::new(-1, 5)), 5);
+    }
+
+    #[test]
+    fn test_direction() {
+        assert_eq!(Direction::Up.turn_left(), Direction::Left);
+        assert_eq!(Direction::Up.turn_right(), Direction::Right);
+        assert!(Direction::ALL.iter().all(|direction| direction.turn_left().turn_right() == *direction));
+    }
+
+    #[test]
+    fn test_parse_grid() {
+        let grid = parse_grid("#@.\n.@#\n");
+        assert_eq!(grid.len(), 6);
+        assert_eq!(grid[&Pos::new(2, 1)], '#');
+        assert_eq!(find_all(&grid, '@'), vec![Pos::new(1, 0), Pos::new(1, 1)]);
+    }
+}
@@ -0,0 +1,142 @@
+use std::fmt;
+use std::io::{IsTerminal, Read};
+use std::path::Path;
+
+// Where the puzzle input of a run comes from
+#[derive(Debug, Clone, PartialEq)]
+pub enum InputSource {
+    File(String),
+    // Text given on the command line, such as an example from the puzzle
+    Inline(String),
+    // Piped input, searched for in the directory instead when it is empty
+    Stdin(String),
+    // Searched for next to the binary's working directory
+    Discover(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum InputError {
+    NotFound{day:u8, searched:Vec<String>},
+    Unreadable{path:String, message:String},
+    EmptyStdin,
+}
+
+impl fmt::Display for InputError {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InputError::NotFound{day, searched} =>
+                write!(f, "no input for day {}, looked for {}; pass --input <path>", day, searched.join(", ")),
+            InputError::Unreadable{path, message} => write!(f, "cannot read {}: {}", path, message),
+            InputError::EmptyStdin => write!(f, "stdin is empty; pipe the puzzle input or pass --input <path>"),
+        }
+    }
+}
+
+// Inputs are named both with and without a leading zero and kept either in the
+// directory itself or in an `inputs` directory below it
+fn candidates(dir:&str, day:u8) -> Vec<String> {
+    let mut names = vec![format!("input_{}.txt", day), format!("input_{:02}.txt", day)];
+    names.dedup();
+    [Path::new(dir).to_path_buf(), Path::new(dir).join("inputs")].iter()
+        .flat_map(|dir| names.iter().map(move |name| dir.join(name)))
+        .map(|path| path.to_string_lossy().into_owned())
+        .collect()
+}
+
+pub fn find_input(dir:&str, day:u8) -> Option<String> {
+    candidates(dir, day).into_iter().find(|path| Path::new(path).is_file())
+}
+
+// Picks stdin when something is piped in and searches the directory when it
+// is a terminal, so that a plain `<day> <part>` never waits for typing. Runs
+// from scripts often have an empty stdin, those fall back to the directory.
+pub fn default_source(dir:&str) -> InputSource {
+    match std::io::stdin().is_terminal() {
+        true => InputSource::Discover(String::from(dir)),
+        false => InputSource::Stdin(String::from(dir)),
+    }
+}
+
+pub fn read_input(source:&InputSource, day:u8) -> Result<String, InputError> {
+    match source {
+        InputSource::File(path) => read_file(path),
+        InputSource::Inline(text) => Ok(unescape_example(text)),
+        InputSource::Stdin(dir) => read_piped(&mut std::io::stdin(), dir, day),
+        InputSource::Discover(dir) => match find_input(dir, day) {
+            Some(path) => read_file(&path),
+            None => Err(InputError::NotFound{day, searched:candidates(dir, day)}),
+        },
+    }
+}
+
+fn read_piped(reader:&mut dyn Read, dir:&str, day:u8) -> Result<String, InputError> {
+    let mut input = String::new();
+    reader.read_to_string(&mut input)
+        .map_err(|err| InputError::Unreadable{path:String::from("stdin"), message:err.to_string()})?;
+    if !input.trim().is_empty() {
+        return Ok(input);
+    }
+    match find_input(dir, day) {
+        Some(path) => read_file(&path),
+        None => Err(InputError::EmptyStdin),
+    }
+}
+
+fn read_file(path:&str) -> Result<String, InputError> {
+    std::fs::read_to_string(path).map_err(|err| InputError::Unreadable{path:String::from(path), message:err.to_string()})
+}
+
+// Examples are mostly several lines long, `\n` in the argument starts a new line
+pub fn unescape_example(text:&str) -> String {
+    text.replace("\\n", "\n")
+}
+
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope.
+    use super::*;
+
+    #[test]
+    fn test_find_input() {
+        assert_eq!(find_input(".", 1), Some(String::from("./input_1.txt")));
+        assert_eq!(find_input(".", 7), Some(String::from("./input_07.txt")));
+        assert_eq!(find_input(".", 17), Some(String::from("./input_17.txt")));
+        assert_eq!(find_input(".", 25), None);
+    }
+
+    #[test]
+    fn test_inputs_directory() {
+        // Unique per process so that concurrent test runs do not share the directory
+        let dir = std::env::temp_dir().join(format!("input_test_{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("inputs")).unwrap();
+        std::fs::write(dir.join("inputs").join("input_03.txt"), "R8,U5\n").unwrap();
+        let dir = dir.to_string_lossy().into_owned();
+
+        let source = InputSource::Discover(dir.clone());
+        let found = read_input(&source, 3);
+        let missing = read_input(&source, 4);
+        std::fs::remove_dir_all(dir).unwrap();
+
+        assert_eq!(found, Ok(String::from("R8,U5\n")));
+        match missing {
+            Err(InputError::NotFound{day:4, searched}) => assert_eq!(searched.len(), 4),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_empty_stdin_falls_back() {
+        assert_eq!(read_piped(&mut "R8,U5\n".as_bytes(), ".", 3), Ok(String::from("R8,U5\n")));
+        assert_eq!(read_piped(&mut std::io::empty(), ".", 1), read_input(&InputSource::Discover(String::from(".")), 1));
+        assert_eq!(read_piped(&mut " \n".as_bytes(), ".", 25), Err(InputError::EmptyStdin));
+    }
+
+    #[test]
+    fn test_inline_and_errors() {
+        assert_eq!(read_input(&InputSource::Inline(String::from("#.\\n.#")), 10), Ok(String::from("#.\n.#")));
+        assert!(matches!(read_input(&InputSource::File(String::from("no_such_input.txt")), 1),
+                         Err(InputError::Unreadable{..})));
+        assert_eq!(InputError::EmptyStdin.to_string(), "stdin is empty; pipe the puzzle input or pass --input <path>");
+    }
+}
@@ -0,0 +1,187 @@
+use crate::util::int_code_computer::*;
+use std::collections::HashMap;
+
+const HEADER:&str = "# intcode cache v1";
+
+// Memoizes a pure program, one that reads its inputs, writes its outputs and
+// halts, by its input vector. With a cache file the results survive between runs,
+// the file is tied to the program by a fingerprint of its opcodes.
+pub struct CachedProgram {
+    template:Program,
+    fingerprint:u64,
+    results:HashMap<Vec<i64>, Vec<i64>>,
+    path:Option<String>,
+    pub hits:u64,
+    pub misses:u64,
+}
+
+impl CachedProgram {
+    pub fn new(opcodes:Vec<i64>) -> CachedProgram {
+        CachedProgram{fingerprint:fingerprint(&opcodes),
+            template:Program::new(opcodes, None),
+            results:HashMap::new(),
+            path:None,
+            hits:0,
+            misses:0}
+    }
+
+    // Loads the cache file if it exists, entries of another program are dropped
+    pub fn with_file(opcodes:Vec<i64>, path:&str) -> Result<CachedProgram, String> {
+        let mut cached = CachedProgram::new(opcodes);
+        cached.path = Some(String::from(path));
+
+        match std::fs::read_to_string(path) {
+            Ok(text) => cached.parse(&text)?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {},
+            Err(err) => return Err(format!("{}: {}", path, err)),
+        }
+        Ok(cached)
+    }
+
+    // Runs the program to completion on the inputs, or returns the earlier result.
+    // Fails if the program wants more input or errors, nothing is cached then.
+    pub fn run(&mut self, inputs:&[i64]) -> Result<Vec<i64>, String> {
+        if let Some(outputs) = self.results.get(inputs) {
+            self.hits += 1;
+            return Ok(outputs.clone());
+        }
+
+        self.misses += 1;
+        let mut program = self.template.clone();
+        for input in inputs {
+            program.add_input(*input);
+        }
+        loop {
+            match program.step() {
+                None => {},
+                Some(StopReason::Halted) => break,
+                Some(StopReason::NeedsInput) => return Err(format!("program wants more than {} inputs", inputs.len())),
+                Some(StopReason::Error(message)) => return Err(message),
+                Some(StopReason::Breakpoint(_)) => {},
+            }
+        }
+
+        let outputs = program.take_output();
+        self.results.insert(inputs.to_vec(), outputs.clone());
+        Ok(outputs)
+    }
+
+    pub fn len(&self) -> usize {
+        self.results.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.results.is_empty()
+    }
+
+    // Writes the cache file, a no-op without one
+    pub fn save(&self) -> Result<(), String> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+
+        let mut entries:Vec<(&Vec<i64>, &Vec<i64>)> = self.results.iter().collect();
+        entries.sort();
+
+        let mut text = format!("{} {:016x}\n", HEADER, self.fingerprint);
+        for (inputs, outputs) in entries {
+            text.push_str(format!("{} -> {}\n", join(inputs), join(outputs)).as_str());
+        }
+        std::fs::write(path, text).map_err(|err| format!("{}: {}", path, err))
+    }
+
+    fn parse(&mut self, text:&str) -> Result<(), String> {
+        let mut lines = text.lines();
+        match lines.next().and_then(|line| line.strip_prefix(HEADER)) {
+            Some(fingerprint) if fingerprint.trim() == format!("{:016x}", self.fingerprint) => {},
+            Some(_) => return Ok(()),
+            None => return Err(String::from("not an intcode cache file")),
+        }
+
+        for (line_no, line) in lines.enumerate() {
+            let entry = line.split_once("->")
+                .and_then(|(inputs, outputs)| Some((split(inputs)?, split(outputs)?)));
+            match entry {
+                Some((inputs, outputs)) => { self.results.insert(inputs, outputs); },
+                None => return Err(format!("line {}: expected '<inputs> -> <outputs>'", line_no + 2)),
+            }
+        }
+        Ok(())
+    }
+}
+
+// FNV-1a, unlike the std hasher it is stable between builds
+fn fingerprint(opcodes:&[i64]) -> u64 {
+    opcodes.iter()
+        .flat_map(|opcode| opcode.to_le_bytes())
+        .fold(0xcbf29ce484222325, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3))
+}
+
+fn join(values:&[i64]) -> String {
+    values.iter().map(|value| value.to_string()).collect::<Vec<String>>().join(",")
+}
+
+fn split(text:&str) -> Option<Vec<i64>> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Some(vec![]);
+    }
+    text.split(',').map(|value| value.trim().parse().ok()).collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope.
+    use super::*;
+
+    // Outputs the sum of two inputs followed by the second one
+    const PROGRAM:[i64;15] = [3,13, 3,14, 1,13,14,13, 4,13, 4,14, 99, 0,0];
+
+    #[test]
+    fn test_memoizes() {
+        let mut cached = CachedProgram::new(PROGRAM.to_vec());
+        assert_eq!(cached.run(&[3, 4]), Ok(vec![7, 4]));
+        assert_eq!(cached.run(&[3, 4]), Ok(vec![7, 4]));
+        assert_eq!(cached.run(&[1, 1]), Ok(vec![2, 1]));
+
+        assert_eq!((cached.hits, cached.misses), (1, 2));
+        assert_eq!(cached.len(), 2);
+    }
+
+    #[test]
+    fn test_missing_input_is_not_cached() {
+        let mut cached = CachedProgram::new(PROGRAM.to_vec());
+        assert!(cached.run(&[3]).is_err());
+        assert!(cached.is_empty());
+    }
+
+    #[test]
+    fn test_cache_file() {
+        // Unique per process so that concurrent test runs do not share the file
+        let path = std::env::temp_dir().join(format!("intcode_cache_test_{}.txt", std::process::id()));
+        let path = path.to_str().unwrap();
+        let _ = std::fs::remove_file(path);
+
+        let mut cached = CachedProgram::with_file(PROGRAM.to_vec(), path).unwrap();
+        cached.run(&[3, 4]).unwrap();
+        cached.run(&[-2, 5]).unwrap();
+        cached.save().unwrap();
+
+        let text = std::fs::read_to_string(path).unwrap();
+        let mut reloaded = CachedProgram::with_file(PROGRAM.to_vec(), path).unwrap();
+        let output = reloaded.run(&[-2, 5]);
+
+        // A different program does not pick up the results
+        let mut other = PROGRAM.to_vec();
+        other[4] = 2;
+        let other = CachedProgram::with_file(other, path).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        assert!(text.ends_with("-2,5 -> 3,5\n3,4 -> 7,4\n"));
+        assert_eq!(output, Ok(vec![3, 5]));
+        assert_eq!((reloaded.hits, reloaded.misses), (1, 0));
+        assert!(other.is_empty());
+    }
+}
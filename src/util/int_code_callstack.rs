@@ -0,0 +1,171 @@
+use crate::util::int_code_computer::*;
+use std::collections::HashMap;
+
+// A function call inferred from the calling convention used by the puzzle programs:
+// the caller stores the return address at [rb+0] and the arguments at [rb+1].., then
+// jumps to the function which starts with `109 n` to allocate its frame. The frame
+// is released by `109 -n` followed by `2106 0 0`, jumping back to [rb+0].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    pub entry:usize,
+    pub frame_size:i64,
+    pub caller_base:i64,
+    pub return_address:i64,
+    // Frame slots after the return address, as they were on entry
+    pub args:Vec<i64>,
+}
+
+pub struct CallStack {
+    memory:HashMap<usize, i64>,
+    frames:Vec<Frame>,
+    last:Option<(usize, i64)>,
+    jumped:bool,
+    pub max_depth:usize,
+    pub calls:usize,
+}
+
+impl CallStack {
+    // Needs the initial memory image to know the values the caller stored
+    pub fn new(opcodes:&[i64]) -> CallStack {
+        CallStack{memory:opcodes.iter().cloned().enumerate().collect(),
+            frames:vec![], last:None, jumped:false, max_depth:0, calls:0}
+    }
+
+    // Innermost frame last
+    pub fn frames(&self) -> &Vec<Frame> {
+        &self.frames
+    }
+
+    pub fn depth(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn backtrace(&self, pc:usize) -> String {
+        let mut output = String::new();
+        let mut pc = pc as i64;
+
+        for (level, frame) in self.frames.iter().rev().enumerate() {
+            let args:Vec<String> = frame.args.iter().map(|arg| arg.to_string()).collect();
+            output.push_str(format!("#{} pc {} in fn@{} (frame {}, base {}) args [{}]\n",
+                                    level, pc, frame.entry, frame.frame_size,
+                                    frame.caller_base + frame.frame_size, args.join(", ")).as_str());
+            pc = frame.return_address;
+        }
+        output.push_str(format!("#{} pc {} in main\n", self.frames.len(), pc).as_str());
+
+        output
+    }
+
+    fn value(&self, addr:i64) -> i64 {
+        *self.memory.get(&(addr as usize)).unwrap_or(&0)
+    }
+}
+
+fn instruction_len(opcode:i64) -> Option<usize> {
+    match opcode % 100 {
+        1 | 2 | 7 | 8 => Some(4),
+        3 | 4 | 9 => Some(2),
+        5 | 6 => Some(3),
+        _ => None,
+    }
+}
+
+impl Observer for CallStack {
+    fn instruction_executed(&mut self, pc:usize, opcode:i64) {
+        self.jumped = match self.last {
+            Some((last_pc, last_opcode)) => instruction_len(last_opcode).is_some_and(|len| last_pc + len != pc),
+            None => false,
+        };
+        self.last = Some((pc, opcode));
+    }
+
+    fn memory_write(&mut self, _pc:usize, addr:usize, _old:i64, new:i64) {
+        self.memory.insert(addr, new);
+    }
+
+    fn relative_base_changed(&mut self, pc:usize, old:i64, new:i64) {
+        if new > old && self.jumped {
+            let args = (old + 1..new).map(|addr| self.value(addr)).collect();
+            self.frames.push(Frame{entry:pc, frame_size:new - old, caller_base:old,
+                return_address:self.value(old), args});
+            self.calls += 1;
+            self.max_depth = self.max_depth.max(self.frames.len());
+        } else if new < old {
+            while self.frames.last().is_some_and(|frame| frame.caller_base >= new) {
+                self.frames.pop();
+            }
+        }
+    }
+}
+
+// Runs the program until it stops and prints a backtrace on errors and breakpoints
+pub fn run_with_backtrace(program:&mut Program, call_stack:&std::sync::Mutex<CallStack>) -> StopReason {
+    let reason = program.run_until_stop();
+    match &reason {
+        StopReason::Error(message) => eprint!("error: {}\n{}", message, call_stack.lock().unwrap().backtrace(program.get_pc())),
+        StopReason::Breakpoint(pc) => eprint!("breakpoint at {}\n{}", pc, call_stack.lock().unwrap().backtrace(*pc)),
+        _ => {},
+    }
+    reason
+}
+
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope.
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    // main calls f(5) which doubles its argument in place, main outputs the result
+    const PROGRAM:[i64;31] = [109,100, 21101,5,0,1, 21101,13,0,0, 1105,1,20, 204,1, 99, 0,0,0,0,
+        109,3, 22201,-2,-2,-2, 109,-3, 2106,0,0];
+
+    #[test]
+    fn test_breakpoint_in_function() {
+        let call_stack = Arc::new(Mutex::new(CallStack::new(&PROGRAM)));
+        let mut program = Program::new(PROGRAM.to_vec(), None);
+        program.add_observer(call_stack.clone());
+        program.add_breakpoint(22);
+
+        assert_eq!(run_with_backtrace(&mut program, &call_stack), StopReason::Breakpoint(22));
+        assert_eq!(call_stack.lock().unwrap().frames(), &vec![
+            Frame{entry:20, frame_size:3, caller_base:100, return_address:13, args:vec![5, 0]},
+        ]);
+        assert_eq!(call_stack.lock().unwrap().backtrace(22),
+                   "#0 pc 22 in fn@20 (frame 3, base 103) args [5, 0]\n#1 pc 13 in main\n");
+
+        assert_eq!(program.run_until_stop(), StopReason::Halted);
+        assert_eq!(call_stack.lock().unwrap().depth(), 0);
+        assert_eq!(program.get_output(), &vec![10]);
+    }
+
+    #[test]
+    fn test_error_stops() {
+        let mut opcodes = PROGRAM.to_vec();
+        opcodes[26] = 77;
+        let call_stack = Arc::new(Mutex::new(CallStack::new(&opcodes)));
+        let mut program = Program::new(opcodes, None);
+        program.add_observer(call_stack.clone());
+
+        match run_with_backtrace(&mut program, &call_stack) {
+            StopReason::Error(_) => assert_eq!(program.get_pc(), 26),
+            other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(call_stack.lock().unwrap().depth(), 1);
+    }
+
+    #[test]
+    fn test_nested_calls_day_19() {
+        let opcodes:Vec<i64> = std::fs::read_to_string("input_19.txt").unwrap()
+            .split(',').map(|op| op.trim().parse().unwrap()).collect();
+        let call_stack = Arc::new(Mutex::new(CallStack::new(&opcodes)));
+        let mut program = Program::new(opcodes, Some(vec![10, 12]));
+        program.add_observer(call_stack.clone());
+
+        assert_eq!(program.run_until_stop(), StopReason::Halted);
+        let call_stack = call_stack.lock().unwrap();
+        assert_eq!(call_stack.depth(), 0);
+        assert!(call_stack.max_depth >= 2);
+        assert!(call_stack.calls > 2);
+    }
+}
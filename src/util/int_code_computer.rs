@@ -1,8 +1,92 @@
-use crate::util::int_code_computer::Operation::{Add, Mult, GetInput, PushOutput, Unknown, HaltProgram, JumpIfEqualToZero, JumpIfNotEqualToZero, SetIfEqual, SetIfLessThan, UpdateRelativeBase};
+use crate::util::int_code_computer::Operation::{Add, Mult, GetInput, PushOutput, Unknown, HaltProgram, JumpIfEqualToZero, JumpIfNotEqualToZero, SetIfEqual, SetIfLessThan, UpdateRelativeBase, Custom};
 use crate::util::int_code_computer::ParameterMode::{ImmediateMode, RelativeMode, PositionMode};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+
+// Callbacks for execution events, all of them optional
+pub trait Observer {
+    fn instruction_executed(&mut self, _pc:usize, _opcode:i64) {}
+    fn memory_read(&mut self, _pc:usize, _addr:usize, _value:i64) {}
+    fn memory_write(&mut self, _pc:usize, _addr:usize, _old:i64, _new:i64) {}
+    fn input_consumed(&mut self, _pc:usize, _value:i64) {}
+    fn output_produced(&mut self, _pc:usize, _value:i64) {}
+    fn relative_base_changed(&mut self, _pc:usize, _old:i64, _new:i64) {}
+    fn halted(&mut self, _pc:usize) {}
+}
+
+// Observers are shared, so a cloned program reports to the same observers
+pub type ObserverHandle = Arc<Mutex<dyn Observer + Send>>;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ParamKind {
+    Read,
+    Write,
+}
+
+// Called with the resolved parameters: values for Read, addresses for Write
+pub type OpHandler = Arc<dyn Fn(&mut Program, &[i64]) + Send + Sync>;
+
+#[derive(Clone)]
+pub struct CustomOp {
+    pub name:String,
+    pub params:Vec<ParamKind>,
+    pub handler:OpHandler,
+}
+
+// Opcodes added on top of the 2019 instruction set, the default registry is empty
+#[derive(Clone, Default)]
+pub struct OpcodeRegistry {
+    ops:HashMap<i64, CustomOp>,
+}
+
+impl OpcodeRegistry {
+    pub fn new() -> OpcodeRegistry {
+        OpcodeRegistry::default()
+    }
+
+    pub fn register(&mut self, opcode:i64, op:CustomOp) -> Result<(), String> {
+        if !(10..99).contains(&opcode) {
+            return Err(format!("opcode {} is reserved or out of range", opcode));
+        }
+        if self.ops.contains_key(&opcode) {
+            return Err(format!("opcode {} is already registered", opcode));
+        }
+        self.ops.insert(opcode, op);
+        Ok(())
+    }
 
+    pub fn get(&self, opcode:i64) -> Option<&CustomOp> {
+        self.ops.get(&opcode)
+    }
+}
 
+#[derive(Debug, Clone, PartialEq)]
+pub enum StopReason {
+    Halted,
+    Breakpoint(usize),
+    NeedsInput,
+    Error(String),
+}
+
+// Copy of the machine state, memory is ordered by address
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    pub pc:usize,
+    pub relative_base:i64,
+    pub program_size:usize,
+    pub memory:BTreeMap<usize,i64>,
+    pub inputs:Vec<i64>,
+    pub outputs:Vec<i64>,
+}
+
+impl Snapshot {
+    pub fn get_memory(&self, addr:usize) -> i64 {
+        *self.memory.get(&addr).unwrap_or(&0)
+    }
+}
+
+#[derive(Clone)]
 pub struct Program {
     pc:usize,
     program_size:usize,
@@ -10,6 +94,9 @@ pub struct Program {
     inputs:Vec<i64>,
     outputs:Vec<i64>,
     relative_base:i64,
+    observers:Vec<ObserverHandle>,
+    registry:Arc<OpcodeRegistry>,
+    breakpoints:HashSet<usize>,
 }
 
 #[derive(Debug)]
@@ -49,7 +136,67 @@ impl Program {
                 None => vec![],
             },
             relative_base:0,
-            outputs:vec![]}
+            outputs:vec![],
+            observers:vec![],
+            registry:Arc::new(OpcodeRegistry::new()),
+            breakpoints:HashSet::new()}
+    }
+
+    pub fn set_registry(&mut self, registry:Arc<OpcodeRegistry>) {
+        self.registry = registry;
+    }
+
+    pub fn get_pc(&self) -> usize {
+        self.pc
+    }
+
+    pub fn get_relative_base(&self) -> i64 {
+        self.relative_base
+    }
+
+    pub fn set_pc(&mut self, pc:usize) {
+        self.pc = pc;
+    }
+
+    pub fn set_relative_base(&mut self, relative_base:i64) {
+        self.relative_base = relative_base;
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot{pc:self.pc,
+            relative_base:self.relative_base,
+            program_size:self.program_size,
+            memory:self.memory.iter().map(|(addr, value)| (*addr, *value)).collect(),
+            inputs:self.inputs.clone(),
+            outputs:self.outputs.clone()}
+    }
+
+    // Observers, registry and breakpoints are kept
+    pub fn restore(&mut self, snapshot:&Snapshot) {
+        self.pc = snapshot.pc;
+        self.relative_base = snapshot.relative_base;
+        self.program_size = snapshot.program_size;
+        self.memory = snapshot.memory.iter().map(|(addr, value)| (*addr, *value)).collect();
+        self.inputs = snapshot.inputs.clone();
+        self.outputs = snapshot.outputs.clone();
+    }
+
+    pub fn add_breakpoint(&mut self, pc:usize) {
+        self.breakpoints.insert(pc);
+    }
+
+    pub fn remove_breakpoint(&mut self, pc:usize) {
+        self.breakpoints.remove(&pc);
+    }
+
+    pub fn add_observer(&mut self, observer:ObserverHandle) {
+        self.observers.push(observer);
+    }
+
+    fn notify<F:FnMut(&mut dyn Observer)>(&self, mut callback:F) {
+        for observer in self.observers.iter() {
+            callback(&mut *observer.lock().unwrap());
+        }
     }
 
     fn get_param_address(&mut self, mask:i64, par_num:usize) -> i64 {
@@ -63,21 +210,26 @@ impl Program {
 
     fn get_param_value(&mut self,mask:i64, par_num:usize) -> i64 {
         let par_value = self.memory[&((self.pc + par_num) as usize)];
-        match get_param_mode(mask, par_num) {
-            PositionMode =>  {
-                self.get_memory(par_value as usize)
-            },
-            ImmediateMode => par_value,
+        let addr = match get_param_mode(mask, par_num) {
+            PositionMode => par_value as usize,
+            ImmediateMode => return par_value,
             RelativeMode => {
                 /*println!("    => Par no:{}, addr:{}, value={}",par_num, par_value + self.relative_base as i64,
                     self.get_memory((par_value + self.relative_base as i64) as usize));*/
-                self.get_memory((par_value + self.relative_base as i64) as usize)
+                (par_value + self.relative_base as i64) as usize
             }
-        }
+        };
+
+        let value = self.get_memory(addr);
+        let pc = self.pc;
+        self.notify(|observer| observer.memory_read(pc, addr, value));
+        value
     }
 
     pub fn set_memory(&mut self,addr:usize, value:i64) {
-        self.memory.insert(addr, value);
+        let old = self.memory.insert(addr, value).unwrap_or(0);
+        let pc = self.pc;
+        self.notify(|observer| observer.memory_write(pc, addr, old, value));
     }
 
     pub fn get_memory(&mut self,addr:usize) -> i64 {
@@ -88,6 +240,11 @@ impl Program {
         self.memory[&addr]
     }
 
+    // Reads a cell without adding it to memory, for tools looking at the machine
+    pub fn peek_memory(&self, addr:usize) -> i64 {
+        *self.memory.get(&addr).unwrap_or(&0)
+    }
+
     pub fn get_input_len(&self) -> usize {
         self.inputs.len()
     }
@@ -125,6 +282,7 @@ impl Program {
             8 => SetIfEqual {param_mask:mask},
             9 => UpdateRelativeBase{param_mask:mask},
             99 => HaltProgram,
+            _ if self.registry.get(opcode).is_some() => Custom {opcode, param_mask:mask},
             _ => Unknown,
         }
     }
@@ -132,6 +290,8 @@ impl Program {
     fn exec_op(&mut self) -> bool {
         let mut cont_execute = true;
         let op = self.next_op();
+        let (pc, opcode) = (self.pc, self.memory[&self.pc]);
+        self.notify(|observer| observer.instruction_executed(pc, opcode));
         //println!("pc = {}, opcode={}, op = {:?}",self.pc, self.memory[&self.pc], op);
         match op {
             Add {param_mask} => {
@@ -158,6 +318,7 @@ impl Program {
                 let out_location = self.get_param_address(param_mask, 1);
 
                 let value = self.inputs.remove(0);
+                self.notify(|observer| observer.input_consumed(pc, value));
                 self.set_memory(out_location as usize, value);
                 self.pc += 2;
                 //println!(" => Read to pos:{} as:{}",out_location, self.get_memory(out_location as usize));
@@ -214,16 +375,34 @@ impl Program {
                 let out_value = self.get_param_value(param_mask, 1);
                 //println!(" => Push output as:{}", out_value);
                 self.outputs.push(out_value);
+                self.notify(|observer| observer.output_produced(pc, out_value));
                 self.pc += 2;
             }
 
             UpdateRelativeBase {param_mask} => {
                 let arg1_val = self.get_param_value(param_mask, 1);
                 //println!(" => Update relative base from:{}, to:{}",self.relative_base, self.relative_base+arg1_val);
+                let old_base = self.relative_base;
                 self.relative_base += arg1_val;
+                self.notify(|observer| observer.relative_base_changed(pc, old_base, old_base + arg1_val));
                 self.pc += 2;
             }
+            Custom {opcode, param_mask} => {
+                let op = self.registry.get(opcode).unwrap().clone();
+                let mut args = vec![];
+                for (i, kind) in op.params.iter().enumerate() {
+                    args.push(match kind {
+                        ParamKind::Read => self.get_param_value(param_mask, i + 1),
+                        ParamKind::Write => self.get_param_address(param_mask, i + 1),
+                    });
+                }
+
+                (op.handler)(self, &args);
+                self.pc += 1 + op.params.len();
+            }
+
             HaltProgram => {
+                self.notify(|observer| observer.halted(pc));
                 cont_execute = false;
             }
 
@@ -243,6 +422,44 @@ impl Program {
         }
     }
 
+    // Executes one instruction, unless the program can not continue
+    pub fn step(&mut self) -> Option<StopReason> {
+        if !self.memory.contains_key(&self.pc) {
+            return Some(StopReason::Error(format!("pc {} outside of memory", self.pc)));
+        }
+
+        match self.next_op() {
+            HaltProgram => {
+                // Executed like any instruction so that observers see the halt
+                self.exec_op();
+                Some(StopReason::Halted)
+            },
+            Unknown => Some(StopReason::Error(format!("unknown opcode {} at pc {}", self.memory[&self.pc], self.pc))),
+            GetInput{..} if self.inputs.is_empty() => Some(StopReason::NeedsInput),
+            _ => {
+                self.exec_op();
+                None
+            }
+        }
+    }
+
+    // Runs until halted, blocked on input, an error or a breakpoint. A breakpoint
+    // at the current pc is stepped over so execution can be resumed.
+    pub fn run_until_stop(&mut self) -> StopReason {
+        if let Some(reason) = self.step() {
+            return reason;
+        }
+
+        loop {
+            if self.breakpoints.contains(&self.pc) {
+                return StopReason::Breakpoint(self.pc);
+            }
+            if let Some(reason) = self.step() {
+                return reason;
+            }
+        }
+    }
+
     pub fn needs_input(&self) -> bool {
         let next_op = self.next_op();
         match next_op {
@@ -282,10 +499,21 @@ impl Program {
         self.outputs.as_ref()
     }
 
+    pub fn push_output(&mut self, value:i64) {
+        self.outputs.push(value);
+        let pc = self.pc;
+        self.notify(|observer| observer.output_produced(pc, value));
+    }
+
     pub fn pop_output(&mut self) -> i64 {
         self.outputs.pop().unwrap()
     }
 
+    // Removes all outputs, oldest first
+    pub fn take_output(&mut self) -> Vec<i64> {
+        std::mem::take(&mut self.outputs)
+    }
+
     pub fn print_opcodes(&self) {
         let mut opcodes_str = String::new();
         for i in 0..self.program_size {
@@ -314,6 +542,7 @@ enum Operation {
     SetIfLessThan {param_mask:i64},
     SetIfEqual {param_mask:i64},
     UpdateRelativeBase{param_mask:i64},
+    Custom{opcode:i64, param_mask:i64},
     HaltProgram,
     Unknown,
 }
@@ -0,0 +1,467 @@
+use crate::util::int_code_computer::*;
+use crate::util::rng::Rng;
+
+// Upper bound on arithmetic instructions executed by a generated program. Every
+// instruction at most doubles the magnitude of a value, so this keeps all values
+// far away from i64 overflow.
+const OP_BUDGET:i64 = 48;
+const MAX_VALUE:i64 = 50;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Param {
+    Position(usize),
+    Immediate(i64),
+    Relative(usize),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Block {
+    Add(Param, Param, usize),
+    Mult(Param, i64, usize),
+    LessThan(Param, Param, usize),
+    Equals(Param, Param, usize),
+    Input(usize),
+    Output(Param),
+    Skip{on_zero:bool, cond:usize, body:Vec<Block>},
+    Loop{count:i64, body:Vec<Block>},
+}
+
+// A generated program in structured form. Data cells are addressed by index and
+// placed right after the code, with the relative base pointing at the first one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FuzzProgram {
+    pub blocks:Vec<Block>,
+    pub data:Vec<i64>,
+    pub inputs:Vec<i64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Execution {
+    pub outputs:Vec<i64>,
+    pub memory:Vec<i64>,
+    pub halted:bool,
+}
+
+pub trait Engine {
+    fn name(&self) -> String;
+    fn execute(&self, opcodes:&[i64], inputs:&[i64], max_steps:usize) -> Execution;
+}
+
+pub struct ReferenceEngine;
+
+impl Engine for ReferenceEngine {
+    fn name(&self) -> String {
+        String::from("reference")
+    }
+
+    fn execute(&self, opcodes:&[i64], inputs:&[i64], max_steps:usize) -> Execution {
+        let mut program = Program::new(opcodes.to_vec(), Some(inputs.to_vec()));
+        let mut steps = 0;
+        while !program.is_halted() && steps < max_steps {
+            program.run_instruction();
+            steps += 1;
+        }
+
+        Execution{outputs:program.get_output().clone(),
+            memory:(0..opcodes.len()).map(|addr| program.get_memory(addr)).collect(),
+            halted:program.is_halted()}
+    }
+}
+
+#[derive(Debug)]
+pub struct Divergence {
+    pub seed:u64,
+    pub program:FuzzProgram,
+    pub engine:String,
+    pub expected:Execution,
+    pub actual:Execution,
+}
+
+impl FuzzProgram {
+    pub fn generate(rng:&mut Rng) -> FuzzProgram {
+        let data_len = rng.range(2, 8) as usize;
+        let data = (0..data_len).map(|_| rng.range(-MAX_VALUE, MAX_VALUE + 1)).collect();
+        let inputs:Vec<i64> = (0..rng.range(0, 4)).map(|_| rng.range(-MAX_VALUE, MAX_VALUE + 1)).collect();
+
+        let mut generator = Generator{rng, data_len, inputs_left:inputs.len(), budget:OP_BUDGET};
+        let blocks = generator.blocks(1, 0);
+
+        FuzzProgram{blocks, data, inputs}
+    }
+
+    pub fn assemble(&self) -> Vec<i64> {
+        // Loop counters live after the data cells
+        let data_base = 2 + code_len(&self.blocks) + 1;
+        let mut asm = Assembler{code:vec![109, data_base as i64], data_base, next_counter:self.data.len()};
+        asm.emit_blocks(&self.blocks);
+        asm.code.push(99);
+
+        let mut opcodes = asm.code;
+        opcodes.extend(self.data.iter());
+        opcodes.resize(data_base + asm.next_counter, 0);
+        opcodes
+    }
+
+    fn max_steps(&self) -> usize {
+        // Generous: every block runs at most OP_BUDGET times plus loop bookkeeping
+        4 * (OP_BUDGET as usize + 4) * (count_blocks(&self.blocks) + 1)
+    }
+}
+
+struct Generator<'a> {
+    rng:&'a mut Rng,
+    data_len:usize,
+    inputs_left:usize,
+    budget:i64,
+}
+
+impl<'a> Generator<'a> {
+    fn blocks(&mut self, multiplier:i64, depth:usize) -> Vec<Block> {
+        let mut blocks = vec![];
+        let len = self.rng.range(1, 6);
+        for _ in 0..len {
+            if self.budget < multiplier {
+                break;
+            }
+            let block = self.block(multiplier, depth);
+            blocks.push(block);
+        }
+        blocks
+    }
+
+    fn block(&mut self, multiplier:i64, depth:usize) -> Block {
+        let kind = self.rng.range(0, 9);
+        match kind {
+            6 if depth < 2 => {
+                let cond = self.cell();
+                let on_zero = self.rng.range(0, 2) == 0;
+                Block::Skip{on_zero, cond, body:self.blocks(multiplier, depth + 1)}
+            },
+            7 if depth < 2 && self.budget >= 2 * multiplier => {
+                let count = self.rng.range(1, 4);
+                Block::Loop{count, body:self.blocks(multiplier * count, depth + 1)}
+            },
+            8 if multiplier == 1 && self.inputs_left > 0 => {
+                self.inputs_left -= 1;
+                Block::Input(self.cell())
+            },
+            _ => {
+                self.budget -= multiplier;
+                match kind % 5 {
+                    0 => Block::Add(self.param(), self.param(), self.cell()),
+                    1 => Block::Mult(self.param(), self.rng.range(-2, 3), self.cell()),
+                    2 => Block::LessThan(self.param(), self.param(), self.cell()),
+                    3 => Block::Equals(self.param(), self.param(), self.cell()),
+                    _ => Block::Output(self.param()),
+                }
+            }
+        }
+    }
+
+    fn cell(&mut self) -> usize {
+        self.rng.range(0, self.data_len as i64) as usize
+    }
+
+    fn param(&mut self) -> Param {
+        match self.rng.range(0, 3) {
+            0 => Param::Position(self.cell()),
+            1 => Param::Immediate(self.rng.range(-MAX_VALUE, MAX_VALUE + 1)),
+            _ => Param::Relative(self.cell()),
+        }
+    }
+}
+
+fn code_len(blocks:&[Block]) -> usize {
+    blocks.iter().map(|block| match block {
+        Block::Input(_) | Block::Output(_) => 2,
+        Block::Skip{body, ..} => 3 + code_len(body),
+        Block::Loop{body, ..} => 4 + code_len(body) + 4 + 3,
+        _ => 4,
+    }).sum()
+}
+
+fn count_blocks(blocks:&[Block]) -> usize {
+    blocks.iter().map(|block| match block {
+        Block::Skip{body, ..} | Block::Loop{body, ..} => 1 + count_blocks(body),
+        _ => 1,
+    }).sum()
+}
+
+struct Assembler {
+    code:Vec<i64>,
+    data_base:usize,
+    next_counter:usize,
+}
+
+impl Assembler {
+    fn emit_blocks(&mut self, blocks:&[Block]) {
+        for block in blocks {
+            self.emit(block);
+        }
+    }
+
+    fn emit(&mut self, block:&Block) {
+        match block {
+            Block::Add(a, b, dest) => self.emit_op(1, &[a.clone(), b.clone(), Param::Position(*dest)]),
+            Block::Mult(a, b, dest) => self.emit_op(2, &[a.clone(), Param::Immediate(*b), Param::Position(*dest)]),
+            Block::LessThan(a, b, dest) => self.emit_op(7, &[a.clone(), b.clone(), Param::Position(*dest)]),
+            Block::Equals(a, b, dest) => self.emit_op(8, &[a.clone(), b.clone(), Param::Position(*dest)]),
+            Block::Input(dest) => self.emit_op(3, &[Param::Relative(*dest)]),
+            Block::Output(a) => self.emit_op(4, std::slice::from_ref(a)),
+            Block::Skip{on_zero, cond, body} => {
+                let end = self.code.len() + 3 + code_len(body);
+                let opcode = if *on_zero { 6 } else { 5 };
+                self.emit_op(opcode, &[Param::Position(*cond), Param::Immediate(end as i64)]);
+                self.emit_blocks(body);
+            },
+            Block::Loop{count, body} => {
+                let counter = self.next_counter;
+                self.next_counter += 1;
+
+                self.emit_op(1, &[Param::Immediate(*count), Param::Immediate(0), Param::Position(counter)]);
+                let start = self.code.len();
+                self.emit_blocks(body);
+                self.emit_op(1, &[Param::Position(counter), Param::Immediate(-1), Param::Position(counter)]);
+                self.emit_op(5, &[Param::Position(counter), Param::Immediate(start as i64)]);
+            },
+        }
+    }
+
+    fn emit_op(&mut self, opcode:i64, params:&[Param]) {
+        let mut instruction = opcode;
+        let mut factor = 100;
+        let mut values = vec![];
+        for param in params {
+            let (mode, value) = match param {
+                Param::Position(cell) => (0, (self.data_base + cell) as i64),
+                Param::Immediate(value) => (1, *value),
+                Param::Relative(cell) => (2, *cell as i64),
+            };
+            instruction += mode * factor;
+            factor *= 10;
+            values.push(value);
+        }
+        self.code.push(instruction);
+        self.code.extend(values);
+    }
+}
+
+// Runs the program on the reference and every other engine, returning the first
+// engine that disagrees together with both executions.
+pub fn compare(program:&FuzzProgram, reference:&dyn Engine, engines:&[&dyn Engine]) -> Option<(String, Execution, Execution)> {
+    let opcodes = program.assemble();
+    let max_steps = program.max_steps();
+    let expected = reference.execute(&opcodes, &program.inputs, max_steps);
+
+    for engine in engines {
+        let actual = engine.execute(&opcodes, &program.inputs, max_steps);
+        if actual != expected {
+            return Some((engine.name(), expected, actual));
+        }
+    }
+
+    None
+}
+
+// Greedily removes and flattens blocks for as long as the engines still disagree
+pub fn minimise(program:FuzzProgram, reference:&dyn Engine, engines:&[&dyn Engine]) -> FuzzProgram {
+    let mut current = program;
+    let mut progress = true;
+
+    while progress {
+        progress = false;
+        for blocks in shrink(&current.blocks) {
+            let candidate = FuzzProgram{blocks, data:current.data.clone(), inputs:current.inputs.clone()};
+            if compare(&candidate, reference, engines).is_some() {
+                current = candidate;
+                progress = true;
+                break;
+            }
+        }
+    }
+
+    current
+}
+
+fn shrink(blocks:&[Block]) -> Vec<Vec<Block>> {
+    let mut candidates = vec![];
+
+    for i in 0..blocks.len() {
+        let mut removed = blocks.to_vec();
+        removed.remove(i);
+        candidates.push(removed);
+
+        let body = match &blocks[i] {
+            Block::Skip{body, ..} => body,
+            Block::Loop{count, body} => {
+                if *count > 1 {
+                    let mut once = blocks.to_vec();
+                    once[i] = Block::Loop{count:1, body:body.clone()};
+                    candidates.push(once);
+                }
+                body
+            },
+            _ => continue,
+        };
+
+        // Replace the block by its body
+        let mut flattened = blocks[..i].to_vec();
+        flattened.extend(body.iter().cloned());
+        flattened.extend(blocks[i + 1..].iter().cloned());
+        candidates.push(flattened);
+
+        for smaller in shrink(body) {
+            let mut nested = blocks.to_vec();
+            nested[i] = with_body(&blocks[i], smaller);
+            candidates.push(nested);
+        }
+    }
+
+    candidates
+}
+
+fn with_body(block:&Block, body:Vec<Block>) -> Block {
+    match block {
+        Block::Skip{on_zero, cond, ..} => Block::Skip{on_zero:*on_zero, cond:*cond, body},
+        Block::Loop{count, ..} => Block::Loop{count:*count, body},
+        _ => block.clone(),
+    }
+}
+
+pub fn fuzz(seed:u64, iterations:usize, reference:&dyn Engine, engines:&[&dyn Engine]) -> Option<Divergence> {
+    let mut rng = Rng::new(seed);
+
+    for _ in 0..iterations {
+        let program_seed = rng.next_u64();
+        if let Some(divergence) = fuzz_one(program_seed, reference, engines) {
+            return Some(divergence);
+        }
+    }
+
+    None
+}
+
+// Entry point for cargo-fuzz style drivers which hand over raw bytes
+pub fn fuzz_bytes(data:&[u8], reference:&dyn Engine, engines:&[&dyn Engine]) -> Option<Divergence> {
+    let seed = data.iter().fold(0xCBF2_9CE4_8422_2325u64, |hash, byte| (hash ^ *byte as u64).wrapping_mul(0x100_0000_01B3));
+    fuzz_one(seed, reference, engines)
+}
+
+fn fuzz_one(seed:u64, reference:&dyn Engine, engines:&[&dyn Engine]) -> Option<Divergence> {
+    let program = FuzzProgram::generate(&mut Rng::new(seed));
+    compare(&program, reference, engines)?;
+
+    let program = minimise(program, reference, engines);
+    let (engine, expected, actual) = compare(&program, reference, engines).unwrap();
+    Some(Divergence{seed, program, engine, expected, actual})
+}
+
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope.
+    use super::*;
+
+    // Independent Vec backed interpreter, optionally with a broken less-than
+    struct VecEngine {
+        broken_less_than:bool,
+    }
+
+    impl Engine for VecEngine {
+        fn name(&self) -> String {
+            format!("vec(broken={})", self.broken_less_than)
+        }
+
+        fn execute(&self, opcodes:&[i64], inputs:&[i64], max_steps:usize) -> Execution {
+            let mut mem = opcodes.to_vec();
+            let mut inputs = inputs.iter();
+            let mut outputs = vec![];
+            let (mut pc, mut base, mut steps) = (0usize, 0i64, 0);
+
+            while mem[pc] != 99 && steps < max_steps {
+                let op = mem[pc] % 100;
+                let addr = |mem:&Vec<i64>, n:usize| -> usize {
+                    match mem[pc] / [100, 1000, 10000][n - 1] % 10 {
+                        1 => pc + n,
+                        2 => (base + mem[pc + n]) as usize,
+                        _ => mem[pc + n] as usize,
+                    }
+                };
+                match op {
+                    1 | 2 | 7 | 8 => {
+                        let (a, b, c) = (mem[addr(&mem, 1)], mem[addr(&mem, 2)], addr(&mem, 3));
+                        mem[c] = match op {
+                            1 => a + b,
+                            2 => a * b,
+                            7 => (a < b || (self.broken_less_than && a == b)) as i64,
+                            _ => (a == b) as i64,
+                        };
+                        pc += 4;
+                    },
+                    3 => { let c = addr(&mem, 1); mem[c] = *inputs.next().unwrap(); pc += 2; },
+                    4 => { outputs.push(mem[addr(&mem, 1)]); pc += 2; },
+                    5 | 6 => {
+                        let a = mem[addr(&mem, 1)];
+                        pc = if (a != 0) == (op == 5) { mem[addr(&mem, 2)] as usize } else { pc + 3 };
+                    },
+                    9 => { base += mem[addr(&mem, 1)]; pc += 2; },
+                    _ => panic!("bad opcode {}", op),
+                }
+                steps += 1;
+            }
+
+            Execution{outputs, halted:mem[pc] == 99, memory:mem}
+        }
+    }
+
+    #[test]
+    fn test_generate_deterministic() {
+        let a = FuzzProgram::generate(&mut Rng::new(42));
+        let b = FuzzProgram::generate(&mut Rng::new(42));
+        assert_eq!(a, b);
+        assert_eq!(a.assemble(), b.assemble());
+    }
+
+    #[test]
+    fn test_generated_programs_halt() {
+        let mut rng = Rng::new(7);
+        for _ in 0..500 {
+            let program = FuzzProgram::generate(&mut rng);
+            let execution = ReferenceEngine.execute(&program.assemble(), &program.inputs, program.max_steps());
+            assert!(execution.halted, "{:?}", program);
+        }
+    }
+
+    #[test]
+    fn test_assemble_loop() {
+        let program = FuzzProgram{
+            blocks:vec![Block::Loop{count:3, body:vec![Block::Add(Param::Position(0), Param::Immediate(2), 0), Block::Output(Param::Relative(0))]}],
+            data:vec![1],
+            inputs:vec![],
+        };
+        let execution = ReferenceEngine.execute(&program.assemble(), &[], 1000);
+        assert_eq!(execution.outputs, vec![3, 5, 7]);
+    }
+
+    #[test]
+    fn test_engines_agree() {
+        let engine = VecEngine{broken_less_than:false};
+        assert!(fuzz(1, 1000, &ReferenceEngine, &[&engine]).is_none());
+    }
+
+    #[test]
+    fn test_finds_and_minimises_divergence() {
+        let engine = VecEngine{broken_less_than:true};
+        let divergence = fuzz(1, 1000, &ReferenceEngine, &[&engine]).unwrap();
+
+        assert_ne!(divergence.expected, divergence.actual);
+        assert!(count_blocks(&divergence.program.blocks) <= 2, "not minimised: {:?}", divergence);
+        assert!(divergence.program.blocks.iter().any(|block| matches!(block, Block::LessThan(..))));
+    }
+
+    #[test]
+    fn test_fuzz_bytes() {
+        let engine = VecEngine{broken_less_than:false};
+        assert!(fuzz_bytes(b"intcode", &ReferenceEngine, &[&engine]).is_none());
+    }
+}
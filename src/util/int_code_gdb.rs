@@ -0,0 +1,476 @@
+use crate::util::int_code_computer::*;
+use std::convert::TryInto;
+use std::io::{self, BufReader, Read, Write};
+use std::net::TcpListener;
+
+// GDB sees a byte addressed target where every cell takes 8 little endian bytes,
+// so cell n lives at address 8 * n. The pc register is a byte address as well,
+// which keeps `break *addr` and `x/gd addr` consistent with each other.
+const CELL:usize = 8;
+
+// Largest packet gdb may send, announced in hex. A memory read answers with two
+// hex digits per byte, so it may ask for half as many bytes.
+const PACKET_SIZE:usize = 0x4000;
+
+const TARGET_XML:&str = "<?xml version=\"1.0\"?>\
+<!DOCTYPE target SYSTEM \"gdb-target.dtd\">\
+<target version=\"1.0\">\
+<feature name=\"org.intcode.core\">\
+<reg name=\"pc\" bitsize=\"64\" type=\"code_ptr\" regnum=\"0\"/>\
+<reg name=\"rb\" bitsize=\"64\" type=\"int64\" regnum=\"1\"/>\
+</feature>\
+</target>";
+
+enum Response {
+    Reply(String),
+    ReplyAndClose(String),
+    Close,
+}
+
+// Serves a single debugger connection for a program. Supported are register and
+// memory access, software breakpoints, single step and continue, plus the monitor
+// commands `input <n>`, `output` and `status` since Intcode io has no gdb equivalent.
+pub struct GdbStub {
+    program:Program,
+    last_stop:String,
+    no_ack:bool,
+}
+
+impl GdbStub {
+    pub fn new(program:Program) -> GdbStub {
+        GdbStub{program, last_stop:String::from("S05"), no_ack:false}
+    }
+
+    pub fn program(&self) -> &Program {
+        &self.program
+    }
+
+    // Waits for one connection on the listener and serves it until it is closed
+    pub fn serve_tcp(&mut self, listener:&TcpListener) -> io::Result<()> {
+        let (stream, _) = listener.accept()?;
+        stream.set_nodelay(true)?;
+        self.serve(stream.try_clone()?, stream)
+    }
+
+    // For `target remote | program ...` style pipes
+    pub fn serve_stdio(&mut self) -> io::Result<()> {
+        self.serve(io::stdin(), io::stdout())
+    }
+
+    pub fn serve<R:Read, W:Write>(&mut self, reader:R, mut writer:W) -> io::Result<()> {
+        let mut bytes = BufReader::new(reader).bytes();
+
+        loop {
+            // Acks and interrupts between packets are skipped, execution is never
+            // long running enough to need interrupting
+            match bytes.next() {
+                Some(Ok(b'$')) => {},
+                Some(Ok(_)) => continue,
+                Some(Err(err)) => return Err(err),
+                None => return Ok(()),
+            }
+
+            let mut data = vec![];
+            loop {
+                match bytes.next() {
+                    Some(Ok(b'#')) => break,
+                    Some(Ok(byte)) => data.push(byte),
+                    Some(Err(err)) => return Err(err),
+                    None => return Ok(()),
+                }
+            }
+            let mut checksum = vec![];
+            for _ in 0..2 {
+                match bytes.next() {
+                    Some(Ok(byte)) => checksum.push(byte),
+                    Some(Err(err)) => return Err(err),
+                    None => return Ok(()),
+                }
+            }
+
+            let expected = std::str::from_utf8(&checksum).ok().and_then(|hex| u8::from_str_radix(hex, 16).ok());
+            if !self.no_ack {
+                if expected != Some(checksum_of(&data)) {
+                    writer.write_all(b"-")?;
+                    writer.flush()?;
+                    continue;
+                }
+                writer.write_all(b"+")?;
+            }
+
+            let packet = String::from_utf8_lossy(&data).into_owned();
+            match self.handle(&packet) {
+                Response::Reply(reply) => write_packet(&mut writer, &reply)?,
+                Response::ReplyAndClose(reply) => {
+                    write_packet(&mut writer, &reply)?;
+                    return Ok(());
+                },
+                Response::Close => {
+                    writer.flush()?;
+                    return Ok(());
+                },
+            }
+
+            if packet == "QStartNoAckMode" {
+                self.no_ack = true;
+            }
+        }
+    }
+
+    fn handle(&mut self, packet:&str) -> Response {
+        let reply = match packet.chars().next() {
+            Some('?') => self.last_stop.clone(),
+            Some('g') => format!("{}{}", encode_word((self.program.get_pc() * CELL) as i64),
+                                 encode_word(self.program.get_relative_base())),
+            Some('G') => {
+                match (decode_word(packet.get(1..17).unwrap_or("")), decode_word(packet.get(17..33).unwrap_or(""))) {
+                    (Some(pc), Some(relative_base)) => {
+                        self.program.set_pc(pc as usize / CELL);
+                        self.program.set_relative_base(relative_base);
+                        String::from("OK")
+                    },
+                    _ => String::from("E01"),
+                }
+            },
+            Some('p') => match usize::from_str_radix(&packet[1..], 16) {
+                Ok(0) => encode_word((self.program.get_pc() * CELL) as i64),
+                Ok(1) => encode_word(self.program.get_relative_base()),
+                _ => String::from("E01"),
+            },
+            Some('P') => self.write_register(&packet[1..]).unwrap_or_else(|| String::from("E01")),
+            Some('m') => self.read_memory(&packet[1..]).unwrap_or_else(|| String::from("E01")),
+            Some('M') => self.write_memory(&packet[1..]).unwrap_or_else(|| String::from("E01")),
+            Some('Z') | Some('z') => self.breakpoint(packet).unwrap_or_else(|| String::from("E01")),
+            Some('s') | Some('c') => {
+                if let Some(addr) = packet.get(1..).filter(|addr| !addr.is_empty()) {
+                    match usize::from_str_radix(addr, 16) {
+                        Ok(addr) => self.program.set_pc(addr / CELL),
+                        Err(_) => return Response::Reply(String::from("E01")),
+                    }
+                }
+                let reason = if packet.starts_with('s') {
+                    self.program.step()
+                } else {
+                    Some(self.program.run_until_stop())
+                };
+                self.last_stop = stop_reply(reason);
+                self.last_stop.clone()
+            },
+            Some('H') | Some('T') => String::from("OK"),
+            Some('k') => return Response::Close,
+            Some('D') => return Response::ReplyAndClose(String::from("OK")),
+            _ => self.query(packet),
+        };
+
+        Response::Reply(reply)
+    }
+
+    fn query(&mut self, packet:&str) -> String {
+        if packet.starts_with("qSupported") {
+            return format!("PacketSize={:x};qXfer:features:read+;QStartNoAckMode+", PACKET_SIZE);
+        }
+        if let Some(range) = packet.strip_prefix("qXfer:features:read:target.xml:") {
+            return match parse_pair(range, ',') {
+                Some((offset, length)) if offset < TARGET_XML.len() => {
+                    let end = (offset + length).min(TARGET_XML.len());
+                    let marker = if end == TARGET_XML.len() { 'l' } else { 'm' };
+                    format!("{}{}", marker, &TARGET_XML[offset..end])
+                },
+                Some(_) => String::from("l"),
+                None => String::from("E01"),
+            };
+        }
+        if let Some(command) = packet.strip_prefix("qRcmd,") {
+            return match decode_hex(command).and_then(|bytes| String::from_utf8(bytes).ok()) {
+                Some(command) => encode_hex(self.monitor(command.trim()).as_bytes()),
+                None => String::from("E01"),
+            };
+        }
+
+        match packet {
+            "QStartNoAckMode" => String::from("OK"),
+            "qAttached" => String::from("1"),
+            "qC" => String::from("QC1"),
+            "qfThreadInfo" => String::from("m1"),
+            "qsThreadInfo" => String::from("l"),
+            _ => String::new(),
+        }
+    }
+
+    fn monitor(&mut self, command:&str) -> String {
+        let words:Vec<&str> = command.split_whitespace().collect();
+        match words.as_slice() {
+            ["input", values @ ..] if !values.is_empty() => {
+                for value in values {
+                    match value.parse::<i64>() {
+                        Ok(value) => self.program.add_input(value),
+                        Err(_) => return format!("bad input '{}'\n", value),
+                    }
+                }
+                String::new()
+            },
+            ["output"] => {
+                let outputs:Vec<String> = self.program.get_output().iter().map(|value| value.to_string()).collect();
+                format!("{}\n", outputs.join(","))
+            },
+            ["status"] => {
+                if self.program.is_halted() {
+                    String::from("halted\n")
+                } else if self.program.needs_input() {
+                    String::from("waiting for input\n")
+                } else {
+                    format!("pc {}, relative base {}\n", self.program.get_pc(), self.program.get_relative_base())
+                }
+            },
+            _ => String::from("commands: input <n>.., output, status\n"),
+        }
+    }
+
+    fn write_register(&mut self, args:&str) -> Option<String> {
+        let (register, value) = args.split_once('=')?;
+        let value = decode_word(value)?;
+        match usize::from_str_radix(register, 16).ok()? {
+            0 => self.program.set_pc(value as usize / CELL),
+            1 => self.program.set_relative_base(value),
+            _ => return None,
+        }
+        Some(String::from("OK"))
+    }
+
+    fn read_memory(&mut self, args:&str) -> Option<String> {
+        let (addr, length) = parse_pair(args, ',')?;
+        if length > PACKET_SIZE / 2 {
+            return None;
+        }
+        let mut bytes = vec![];
+        for byte in addr..addr.checked_add(length)? {
+            bytes.push(self.program.peek_memory(byte / CELL).to_le_bytes()[byte % CELL]);
+        }
+        Some(encode_hex(&bytes))
+    }
+
+    fn write_memory(&mut self, args:&str) -> Option<String> {
+        let (range, data) = args.split_once(':')?;
+        let (addr, length) = parse_pair(range, ',')?;
+        let data = decode_hex(data)?;
+        if data.len() != length || addr.checked_add(length).is_none() {
+            return None;
+        }
+
+        for (offset, value) in data.iter().enumerate() {
+            let byte = addr + offset;
+            let old = self.program.peek_memory(byte / CELL);
+            let mut cell = old.to_le_bytes();
+            cell[byte % CELL] = *value;
+            if i64::from_le_bytes(cell) != old {
+                self.program.set_memory(byte / CELL, i64::from_le_bytes(cell));
+            }
+        }
+        Some(String::from("OK"))
+    }
+
+    // Software and hardware breakpoints are the same thing here, watchpoints are
+    // not supported
+    fn breakpoint(&mut self, packet:&str) -> Option<String> {
+        let fields:Vec<&str> = packet[1..].split(',').collect();
+        if fields.len() < 2 || !["0", "1"].contains(&fields[0]) {
+            return Some(String::new());
+        }
+
+        let addr = usize::from_str_radix(fields[1], 16).ok()?;
+        if !addr.is_multiple_of(CELL) {
+            return None;
+        }
+        if packet.starts_with('Z') {
+            self.program.add_breakpoint(addr / CELL);
+        } else {
+            self.program.remove_breakpoint(addr / CELL);
+        }
+        Some(String::from("OK"))
+    }
+}
+
+fn stop_reply(reason:Option<StopReason>) -> String {
+    match reason {
+        None | Some(StopReason::Breakpoint(_)) | Some(StopReason::NeedsInput) => String::from("S05"),
+        Some(StopReason::Halted) => String::from("W00"),
+        Some(StopReason::Error(_)) => String::from("S04"),
+    }
+}
+
+fn checksum_of(data:&[u8]) -> u8 {
+    data.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte))
+}
+
+fn write_packet<W:Write>(writer:&mut W, data:&str) -> io::Result<()> {
+    write!(writer, "${}#{:02x}", data, checksum_of(data.as_bytes()))?;
+    writer.flush()
+}
+
+fn parse_pair(text:&str, separator:char) -> Option<(usize, usize)> {
+    let (first, second) = text.split_once(separator)?;
+    Some((usize::from_str_radix(first, 16).ok()?, usize::from_str_radix(second, 16).ok()?))
+}
+
+fn encode_hex(bytes:&[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn decode_hex(text:&str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..text.len()).step_by(2)
+        .map(|index| text.get(index..index + 2).and_then(|hex| u8::from_str_radix(hex, 16).ok()))
+        .collect()
+}
+
+fn encode_word(value:i64) -> String {
+    encode_hex(&value.to_le_bytes())
+}
+
+fn decode_word(text:&str) -> Option<i64> {
+    let bytes:[u8;8] = decode_hex(text)?.try_into().ok()?;
+    Some(i64::from_le_bytes(bytes))
+}
+
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope.
+    use super::*;
+    use std::io::{BufRead, Cursor};
+    use std::net::TcpStream;
+
+    // Counts mem[12] down from 3, outputting each value, then outputs -1
+    const PROGRAM:[i64;13] = [4,12, 1001,12,-1,12, 1005,12,0, 104,-1, 99, 3];
+
+    fn packet(data:&str) -> String {
+        format!("${}#{:02x}", data, checksum_of(data.as_bytes()))
+    }
+
+    // Splits the server output into the payloads of its packets
+    fn replies(output:&[u8]) -> Vec<String> {
+        let output = String::from_utf8_lossy(output);
+        output.split('$').skip(1)
+            .map(|packet| {
+                let (data, checksum) = packet.split_once('#').unwrap();
+                assert_eq!(u8::from_str_radix(&checksum[..2], 16).unwrap(), checksum_of(data.as_bytes()));
+                String::from(data)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_scripted_session() {
+        let script:String = ["qSupported:multiprocess+", "?", "Z0,48,1", "c", "g", "m60,8", "s", "p0",
+            &format!("qRcmd,{}", encode_hex(b"output")), "z0,48,1", "c", "k"]
+            .iter().map(|data| format!("+{}", packet(data))).collect();
+
+        let mut stub = GdbStub::new(Program::new(PROGRAM.to_vec(), None));
+        let mut output = vec![];
+        stub.serve(Cursor::new(script), &mut output).unwrap();
+
+        assert_eq!(replies(&output), vec![
+            "PacketSize=4000;qXfer:features:read+;QStartNoAckMode+",
+            "S05",
+            "OK",
+            "S05",
+            "48000000000000000000000000000000",
+            "0000000000000000",
+            "S05",
+            "5800000000000000",
+            encode_hex(b"3,2,1,-1\n").as_str(),
+            "OK",
+            "W00",
+        ]);
+        assert_eq!(stub.program().get_output(), &vec![3, 2, 1, -1]);
+    }
+
+    #[test]
+    fn test_bad_checksum_is_nacked() {
+        let mut stub = GdbStub::new(Program::new(PROGRAM.to_vec(), None));
+        let mut output = vec![];
+        stub.serve(Cursor::new(format!("$g#00{}", packet("qAttached"))), &mut output).unwrap();
+
+        assert_eq!(String::from_utf8(output).unwrap(), format!("-+{}", packet("1")));
+    }
+
+    #[test]
+    fn test_bad_memory_ranges() {
+        let mut stub = GdbStub::new(Program::new(PROGRAM.to_vec(), None));
+        for packet in ["mffffffffffffff00,200", "m0,100000", "Mfffffffffffffffe,4:00000000"] {
+            match stub.handle(packet) {
+                Response::Reply(reply) => assert_eq!(reply, "E01", "{}", packet),
+                _ => panic!("no reply"),
+            }
+        }
+        match stub.handle("m0,2000") {
+            Response::Reply(reply) => assert_eq!(reply.len(), 0x4000),
+            _ => panic!("no reply"),
+        }
+    }
+
+    #[test]
+    fn test_memory_reads_leave_memory_alone() {
+        let mut stub = GdbStub::new(Program::new(PROGRAM.to_vec(), None));
+        let before = stub.program().snapshot();
+        for packet in ["m0,2000", "M100,8:0000000000000000"] {
+            assert!(matches!(stub.handle(packet), Response::Reply(_)), "{}", packet);
+        }
+        assert_eq!(stub.program().snapshot(), before);
+    }
+
+    #[test]
+    fn test_target_description() {
+        let mut stub = GdbStub::new(Program::new(PROGRAM.to_vec(), None));
+        let first = match stub.handle("qXfer:features:read:target.xml:0,20") {
+            Response::Reply(reply) => reply,
+            _ => panic!("no reply"),
+        };
+        assert_eq!(first, format!("m{}", &TARGET_XML[..0x20]));
+
+        let rest = match stub.handle(&format!("qXfer:features:read:target.xml:20,{:x}", TARGET_XML.len())) {
+            Response::Reply(reply) => reply,
+            _ => panic!("no reply"),
+        };
+        assert_eq!(rest, format!("l{}", &TARGET_XML[0x20..]));
+    }
+
+    #[test]
+    fn test_tcp_client() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = std::thread::spawn(move || {
+            let mut stub = GdbStub::new(Program::new(PROGRAM.to_vec(), None));
+            stub.serve_tcp(&listener).unwrap();
+            stub.program().get_output().clone()
+        });
+
+        let stream = TcpStream::connect(addr).unwrap();
+        let mut reader = std::io::BufReader::new(stream.try_clone().unwrap());
+        let mut writer = stream;
+        let mut request = |data:&str| -> String {
+            writer.write_all(packet(data).as_bytes()).unwrap();
+            let mut reply = vec![];
+            reader.read_until(b'#', &mut reply).unwrap();
+            let mut checksum = [0u8;2];
+            reader.read_exact(&mut checksum).unwrap();
+            writer.write_all(b"+").unwrap();
+            replies(&[reply, checksum.to_vec()].concat()).pop().unwrap()
+        };
+
+        // Start the count at 1 instead of 3, stop before the final output
+        assert_eq!(request("M60,8:0100000000000000"), "OK");
+        assert_eq!(request("m60,8"), "0100000000000000");
+        assert_eq!(request("Z0,48,1"), "OK");
+        assert_eq!(request("c"), "S05");
+        assert_eq!(request("p0"), "4800000000000000");
+        assert_eq!(request("P1=0a00000000000000"), "OK");
+        assert_eq!(request("g"), "48000000000000000a00000000000000");
+        assert_eq!(request("c"), "W00");
+        assert_eq!(request("D"), "OK");
+
+        assert_eq!(server.join().unwrap(), vec![1, -1]);
+    }
+}
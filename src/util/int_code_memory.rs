@@ -0,0 +1,189 @@
+use crate::util::int_code_computer::*;
+use std::collections::{BTreeSet, HashMap};
+
+const RAMP:&[u8] = b" .:-=+*#%@";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CellChange {
+    pub addr:usize,
+    pub before:i64,
+    pub after:i64,
+}
+
+// Cells that differ between two snapshots, cells never touched count as 0
+pub fn diff(before:&Snapshot, after:&Snapshot) -> Vec<CellChange> {
+    let addresses:BTreeSet<usize> = before.memory.keys().chain(after.memory.keys()).cloned().collect();
+
+    addresses.into_iter()
+        .map(|addr| CellChange{addr, before:before.get_memory(addr), after:after.get_memory(addr)})
+        .filter(|change| change.before != change.after)
+        .collect()
+}
+
+pub fn format_diff(changes:&[CellChange]) -> String {
+    let mut output = String::new();
+    for change in changes {
+        output.push_str(format!("{:>6}: {} -> {}\n", change.addr, change.before, change.after).as_str());
+    }
+    output
+}
+
+// Counts writes per address, attach it before running to get a heat map
+#[derive(Default)]
+pub struct WriteHeat {
+    pub writes:HashMap<usize, usize>,
+}
+
+impl WriteHeat {
+    pub fn new() -> WriteHeat {
+        WriteHeat::default()
+    }
+
+    pub fn get(&self, addr:usize) -> usize {
+        *self.writes.get(&addr).unwrap_or(&0)
+    }
+
+    // Heat in 0..=1 on a log scale, so a few hot loop counters do not hide the rest
+    fn level(&self, addr:usize, max:usize) -> f64 {
+        match self.get(addr) {
+            0 => 0.0,
+            n => (n as f64 + 1.0).ln() / (max as f64 + 1.0).ln(),
+        }
+    }
+
+    fn max(&self) -> usize {
+        *self.writes.values().max().unwrap_or(&1)
+    }
+}
+
+impl Observer for WriteHeat {
+    fn memory_write(&mut self, _pc:usize, addr:usize, _old:i64, _new:i64) {
+        *self.writes.entry(addr).or_insert(0) += 1;
+    }
+}
+
+fn memory_len(snapshot:&Snapshot, heat:&WriteHeat) -> usize {
+    let memory_end = snapshot.memory.keys().max().map_or(0, |addr| addr + 1);
+    let heat_end = heat.writes.keys().max().map_or(0, |addr| addr + 1);
+    memory_end.max(heat_end)
+}
+
+// One character per cell, '@' for the most written cells and ' ' for never written.
+// A width of 0 is taken as 1.
+pub fn render_ascii(snapshot:&Snapshot, heat:&WriteHeat, width:usize) -> String {
+    let width = width.max(1);
+    let len = memory_len(snapshot, heat);
+    let max = heat.max();
+    let mut output = String::new();
+
+    for row in 0..len.div_ceil(width) {
+        output.push_str(format!("{:>6} ", row * width).as_str());
+        for addr in row * width..(row * width + width).min(len) {
+            let level = heat.level(addr, max);
+            let index = (level * (RAMP.len() - 1) as f64).round() as usize;
+            output.push(RAMP[index] as char);
+        }
+        output.push('\n');
+    }
+
+    output
+}
+
+// Binary PPM with one pixel per cell: program image in blue, written cells from
+// dark red to yellow by write count, untouched memory black. A width of 0 is
+// taken as 1.
+pub fn render_ppm(snapshot:&Snapshot, heat:&WriteHeat, width:usize) -> Vec<u8> {
+    let width = width.max(1);
+    let len = memory_len(snapshot, heat);
+    let height = len.div_ceil(width).max(1);
+    let max = heat.max();
+
+    let mut image = format!("P6\n{} {}\n255\n", width, height).into_bytes();
+    for addr in 0..width * height {
+        let level = heat.level(addr, max);
+        let pixel = if level > 0.0 {
+            [(96.0 + 159.0 * level) as u8, (255.0 * level) as u8, 0]
+        } else if addr < snapshot.program_size {
+            [0, 0, 96]
+        } else {
+            [0, 0, 0]
+        };
+        image.extend_from_slice(&pixel);
+    }
+
+    image
+}
+
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope.
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    // Counts mem[12] down from 3 while adding it to mem[13]
+    const PROGRAM:[i64;14] = [1,12,13,13, 1001,12,-1,12, 1005,12,0, 99, 3,0];
+
+    #[test]
+    fn test_diff() {
+        let mut program = Program::new(PROGRAM.to_vec(), None);
+        let before = program.snapshot();
+        program.run();
+        let after = program.snapshot();
+
+        let changes = diff(&before, &after);
+        assert_eq!(changes, vec![
+            CellChange{addr:12, before:3, after:0},
+            CellChange{addr:13, before:0, after:6},
+        ]);
+        assert_eq!(format_diff(&changes), "    12: 3 -> 0\n    13: 0 -> 6\n");
+    }
+
+    #[test]
+    fn test_restore() {
+        let mut program = Program::new(PROGRAM.to_vec(), None);
+        program.run_instruction();
+        let snapshot = program.snapshot();
+        program.run();
+        assert_eq!(program.get_memory(13), 6);
+
+        program.restore(&snapshot);
+        assert_eq!(program.get_pc(), 4);
+        assert_eq!(program.get_memory(13), 3);
+        program.run();
+        assert_eq!(program.get_memory(13), 6);
+    }
+
+    #[test]
+    fn test_render() {
+        let heat = Arc::new(Mutex::new(WriteHeat::new()));
+        let mut program = Program::new(PROGRAM.to_vec(), None);
+        program.add_observer(heat.clone());
+        program.run();
+
+        let heat = heat.lock().unwrap();
+        assert_eq!(heat.get(12), 3);
+        assert_eq!(heat.get(13), 3);
+
+        let snapshot = program.snapshot();
+        assert_eq!(render_ascii(&snapshot, &heat, 8), "     0         \n     8     @@\n");
+
+        let image = render_ppm(&snapshot, &heat, 8);
+        assert!(image.starts_with(b"P6\n8 2\n255\n"));
+        assert_eq!(image.len(), 11 + 8 * 2 * 3);
+        assert_eq!(&image[11..14], &[0, 0, 96]);
+        assert_eq!(&image[11 + 12 * 3..11 + 13 * 3], &[255, 255, 0]);
+    }
+
+    #[test]
+    fn test_render_zero_width() {
+        let mut program = Program::new(vec![1101,1,1,3,99], None);
+        program.run();
+        let snapshot = program.snapshot();
+        let heat = WriteHeat::new();
+
+        assert_eq!(render_ascii(&snapshot, &heat, 0), render_ascii(&snapshot, &heat, 1));
+        assert_eq!(render_ascii(&snapshot, &heat, 0).lines().count(), 5);
+        assert!(render_ppm(&snapshot, &heat, 0).starts_with(b"P6\n1 5\n255\n"));
+    }
+}
@@ -0,0 +1,210 @@
+use crate::util::int_code_computer::*;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+// Keeps a human readable log of every event, optionally capped in length
+pub struct Tracer {
+    pub lines:Vec<String>,
+    limit:Option<usize>,
+}
+
+impl Tracer {
+    pub fn new(limit:Option<usize>) -> Tracer {
+        Tracer{lines:vec![], limit}
+    }
+
+    fn log(&mut self, line:String) {
+        if self.limit.is_none_or(|limit| self.lines.len() < limit) {
+            self.lines.push(line);
+        }
+    }
+}
+
+impl Observer for Tracer {
+    fn instruction_executed(&mut self, pc:usize, opcode:i64) {
+        self.log(format!("{:>5}: exec {}", pc, opcode));
+    }
+
+    fn memory_read(&mut self, pc:usize, addr:usize, value:i64) {
+        self.log(format!("{:>5}: read [{}] = {}", pc, addr, value));
+    }
+
+    fn memory_write(&mut self, pc:usize, addr:usize, old:i64, new:i64) {
+        self.log(format!("{:>5}: write [{}] {} -> {}", pc, addr, old, new));
+    }
+
+    fn input_consumed(&mut self, pc:usize, value:i64) {
+        self.log(format!("{:>5}: input {}", pc, value));
+    }
+
+    fn output_produced(&mut self, pc:usize, value:i64) {
+        self.log(format!("{:>5}: output {}", pc, value));
+    }
+
+    fn relative_base_changed(&mut self, pc:usize, old:i64, new:i64) {
+        self.log(format!("{:>5}: relative base {} -> {}", pc, old, new));
+    }
+
+    fn halted(&mut self, pc:usize) {
+        self.log(format!("{:>5}: halt", pc));
+    }
+}
+
+// Counts executed instructions per opcode and per address
+#[derive(Default)]
+pub struct Profiler {
+    pub instructions:usize,
+    pub per_opcode:BTreeMap<i64, usize>,
+    pub per_pc:HashMap<usize, usize>,
+}
+
+impl Profiler {
+    pub fn new() -> Profiler {
+        Profiler::default()
+    }
+
+    // Addresses executed the most, hottest first
+    pub fn hot_spots(&self, count:usize) -> Vec<(usize, usize)> {
+        let mut spots:Vec<(usize, usize)> = self.per_pc.iter().map(|(pc, hits)| (*pc, *hits)).collect();
+        spots.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        spots.truncate(count);
+        spots
+    }
+}
+
+impl Observer for Profiler {
+    fn instruction_executed(&mut self, pc:usize, opcode:i64) {
+        self.instructions += 1;
+        *self.per_opcode.entry(opcode % 100).or_insert(0) += 1;
+        *self.per_pc.entry(pc).or_insert(0) += 1;
+    }
+}
+
+// Records which instruction addresses were executed
+#[derive(Default)]
+pub struct Coverage {
+    pub executed:HashSet<usize>,
+}
+
+impl Coverage {
+    pub fn new() -> Coverage {
+        Coverage::default()
+    }
+
+    pub fn is_covered(&self, pc:usize) -> bool {
+        self.executed.contains(&pc)
+    }
+}
+
+impl Observer for Coverage {
+    fn instruction_executed(&mut self, pc:usize, _opcode:i64) {
+        self.executed.insert(pc);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WatchHit {
+    Read{pc:usize, addr:usize, value:i64},
+    Write{pc:usize, addr:usize, old:i64, new:i64},
+}
+
+// Records reads and writes of a set of addresses
+pub struct Watchpoint {
+    addresses:HashSet<usize>,
+    pub hits:Vec<WatchHit>,
+}
+
+impl Watchpoint {
+    pub fn new(addresses:&[usize]) -> Watchpoint {
+        Watchpoint{addresses:addresses.iter().cloned().collect(), hits:vec![]}
+    }
+}
+
+impl Observer for Watchpoint {
+    fn memory_read(&mut self, pc:usize, addr:usize, value:i64) {
+        if self.addresses.contains(&addr) {
+            self.hits.push(WatchHit::Read{pc, addr, value});
+        }
+    }
+
+    fn memory_write(&mut self, pc:usize, addr:usize, old:i64, new:i64) {
+        if self.addresses.contains(&addr) {
+            self.hits.push(WatchHit::Write{pc, addr, old, new});
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope.
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_profiler_and_coverage() {
+        // Counts mem[12] down from 3, outputting each value
+        let opcodes = vec![4,12,1001,12,-1,12,1005,12,0,104,-1,99,3];
+        let profiler = Arc::new(Mutex::new(Profiler::new()));
+        let coverage = Arc::new(Mutex::new(Coverage::new()));
+
+        let mut program = Program::new(opcodes, None);
+        program.add_observer(profiler.clone());
+        program.add_observer(coverage.clone());
+        program.run();
+
+        assert_eq!(program.get_output(), &vec![3,2,1,-1]);
+
+        let profiler = profiler.lock().unwrap();
+        assert_eq!(profiler.instructions, 3 * 3 + 2);
+        assert_eq!(profiler.per_opcode[&4], 4);
+        assert_eq!(profiler.hot_spots(1), vec![(0, 3)]);
+
+        let coverage = coverage.lock().unwrap();
+        assert!(coverage.is_covered(9));
+        assert!(!coverage.is_covered(1));
+        assert_eq!(coverage.executed.len(), 5);
+    }
+
+    #[test]
+    fn test_watchpoint() {
+        let opcodes = vec![3,9,1001,9,5,9,109,7,99,0];
+        let watchpoint = Arc::new(Mutex::new(Watchpoint::new(&[9])));
+        let tracer = Arc::new(Mutex::new(Tracer::new(None)));
+
+        let mut program = Program::new(opcodes, Some(vec![10]));
+        program.add_observer(watchpoint.clone());
+        program.add_observer(tracer.clone());
+        program.run();
+
+        assert_eq!(watchpoint.lock().unwrap().hits, vec![
+            WatchHit::Write{pc:0, addr:9, old:0, new:10},
+            WatchHit::Read{pc:2, addr:9, value:10},
+            WatchHit::Write{pc:2, addr:9, old:10, new:15},
+        ]);
+
+        let lines = &tracer.lock().unwrap().lines;
+        assert!(lines.contains(&String::from("    0: input 10")));
+        assert!(lines.contains(&String::from("    6: relative base 0 -> 7")));
+        assert_eq!(lines.last().unwrap(), "    8: halt");
+    }
+
+    #[test]
+    fn test_tracer_limit() {
+        let tracer = Arc::new(Mutex::new(Tracer::new(Some(2))));
+        let mut program = Program::new(vec![1101,1,1,5,99,0], None);
+        program.add_observer(tracer.clone());
+        program.run();
+
+        assert_eq!(tracer.lock().unwrap().lines, vec!["    0: exec 1101", "    0: write [5] 0 -> 2"]);
+    }
+
+    #[test]
+    fn test_halt_seen_by_run_until_stop() {
+        let tracer = Arc::new(Mutex::new(Tracer::new(None)));
+        let mut program = Program::new(vec![1101,1,1,5,99,0], None);
+        program.add_observer(tracer.clone());
+
+        assert_eq!(program.run_until_stop(), StopReason::Halted);
+        assert_eq!(tracer.lock().unwrap().lines[2..], ["    4: exec 99", "    4: halt"]);
+    }
+}
@@ -0,0 +1,87 @@
+use crate::util::int_code_computer::*;
+use crate::util::rng::Rng;
+use std::sync::{Arc, Mutex};
+
+// Prints its parameter to stderr: <opcode> value
+pub fn debug_print() -> CustomOp {
+    CustomOp{name:String::from("debug"),
+        params:vec![ParamKind::Read],
+        handler:Arc::new(|program, args| eprintln!("[intcode pc={}] {}", program.get_pc(), args[0]))}
+}
+
+// Stores a number in 0..bound: <opcode> bound dest
+pub fn random(seed:u64) -> CustomOp {
+    let rng = Mutex::new(Rng::new(seed));
+    CustomOp{name:String::from("random"),
+        params:vec![ParamKind::Read, ParamKind::Write],
+        handler:Arc::new(move |program, args| {
+            let value = rng.lock().unwrap().range(0, args[0].max(1));
+            program.set_memory(args[1] as usize, value);
+        })}
+}
+
+// Calls into the host with one argument and stores the result: <opcode> arg dest
+pub fn host_call<F>(name:&str, function:F) -> CustomOp where F:Fn(i64) -> i64 + Send + Sync + 'static {
+    CustomOp{name:String::from(name),
+        params:vec![ParamKind::Read, ParamKind::Write],
+        handler:Arc::new(move |program, args| program.set_memory(args[1] as usize, function(args[0])))}
+}
+
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope.
+    use super::*;
+
+    fn run(opcodes:Vec<i64>, registry:&Arc<OpcodeRegistry>) -> Program {
+        let mut program = Program::new(opcodes, None);
+        program.set_registry(registry.clone());
+        program.run();
+        program
+    }
+
+    #[test]
+    fn test_host_call() {
+        let mut registry = OpcodeRegistry::new();
+        registry.register(42, host_call("square", |n| n * n)).unwrap();
+        let registry = Arc::new(registry);
+
+        // mem[10] = square(7), then square(mem[10]) is output via relative mode
+        let program = run(vec![10142,7,10,242,10,0,4,0,99,0,0], &registry);
+        assert_eq!(program.get_output(), &vec![2401]);
+    }
+
+    #[test]
+    fn test_random_is_seeded() {
+        let mut registry = OpcodeRegistry::new();
+        registry.register(50, random(3)).unwrap();
+        registry.register(51, debug_print()).unwrap();
+        let registry = Arc::new(registry);
+
+        let opcodes = vec![150,6,9,51,9,99,0,0,0,0];
+        let first = run(opcodes.clone(), &registry).get_memory(9);
+        assert!((0..6).contains(&first));
+
+        let mut other = OpcodeRegistry::new();
+        other.register(50, random(3)).unwrap();
+        other.register(51, debug_print()).unwrap();
+        assert_eq!(run(opcodes, &Arc::new(other)).get_memory(9), first);
+    }
+
+    #[test]
+    fn test_register_rejects_builtins() {
+        let mut registry = OpcodeRegistry::new();
+        assert!(registry.register(1, debug_print()).is_err());
+        assert!(registry.register(99, debug_print()).is_err());
+        assert!(registry.register(100, debug_print()).is_err());
+        assert!(registry.register(20, debug_print()).is_ok());
+        assert!(registry.register(20, debug_print()).is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "Uknown op")]
+    fn test_default_registry_is_2019_set() {
+        let mut program = Program::new(vec![42,0,0,99], None);
+        program.run();
+    }
+}
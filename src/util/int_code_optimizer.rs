@@ -0,0 +1,403 @@
+use std::collections::{BTreeSet, HashSet};
+use std::fmt;
+
+// Static view of a program. Addresses are never relocated, so every rewrite keeps
+// the instruction length, and the program only shrinks by dropping a trailing
+// region that is provably never executed or read.
+#[derive(Debug, Default, PartialEq)]
+struct Analysis {
+    // Start address of every reachable instruction
+    instructions:BTreeSet<usize>,
+    written:HashSet<usize>,
+    read:HashSet<usize>,
+    writes_anywhere:bool,
+    reads_anywhere:bool,
+    indirect_jumps:bool,
+    // An opcode may be rewritten at runtime, so the code can not be decoded
+    modified_code:bool,
+}
+
+impl Analysis {
+    // Every cell of every reachable instruction
+    fn code_cells(&self, opcodes:&[i64]) -> HashSet<usize> {
+        self.instructions.iter()
+            .flat_map(|pc| *pc..=*pc + params(cell(opcodes, *pc)).unwrap_or(0))
+            .collect()
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Report {
+    pub instructions:usize,
+    // Half open address ranges never executed
+    pub unreachable:Vec<(usize, usize)>,
+    pub write_only:Vec<usize>,
+    pub folded:Vec<usize>,
+    pub immediates:usize,
+    // Instructions left alone since they may be modified or read as data
+    pub refused:Vec<usize>,
+    pub removed:usize,
+    pub writes_anywhere:bool,
+    pub reads_anywhere:bool,
+    pub indirect_jumps:bool,
+    pub modified_code:bool,
+}
+
+fn params(opcode:i64) -> Option<usize> {
+    match opcode % 100 {
+        1 | 2 | 7 | 8 => Some(3),
+        3 | 4 | 9 => Some(1),
+        5 | 6 => Some(2),
+        99 => Some(0),
+        _ => None,
+    }
+}
+
+fn mode(opcode:i64, par_num:usize) -> i64 {
+    opcode / 10_i64.pow(par_num as u32 + 1) % 10
+}
+
+fn is_write(opcode:i64, par_num:usize) -> bool {
+    match opcode % 100 {
+        1 | 2 | 7 | 8 => par_num == 3,
+        3 => par_num == 1,
+        _ => false,
+    }
+}
+
+fn cell(opcodes:&[i64], addr:usize) -> i64 {
+    *opcodes.get(addr).unwrap_or(&0)
+}
+
+// Reachability and the set of written cells depend on each other, so the walk
+// is repeated until neither changes. Both only ever grow, as fewer constant
+// cells can make code unreachable again and the iteration would not settle.
+// Once a write may land anywhere no cell is constant any more.
+fn analyse(opcodes:&[i64]) -> Analysis {
+    let mut written:HashSet<usize> = HashSet::new();
+    let mut writes_anywhere = false;
+    let mut starts = vec![0];
+
+    loop {
+        let mut analysis = walk(opcodes, &written, writes_anywhere, &starts);
+
+        // Assume indirect jumps only land on addresses that appear as constants,
+        // such as the return addresses stored by the calling convention
+        let mut next_starts = starts.clone();
+        if analysis.indirect_jumps {
+            next_starts.extend(opcodes.iter()
+                .filter(|value| **value >= 0 && (**value as usize) < opcodes.len())
+                .map(|value| *value as usize));
+            next_starts.sort();
+            next_starts.dedup();
+        }
+
+        let next_written:HashSet<usize> = written.union(&analysis.written).cloned().collect();
+        let next_writes_anywhere = writes_anywhere || analysis.writes_anywhere;
+        if next_written == written && next_starts == starts && next_writes_anywhere == writes_anywhere {
+            analysis.written = written;
+            return analysis;
+        }
+        written = next_written;
+        writes_anywhere = next_writes_anywhere;
+        starts = next_starts;
+    }
+}
+
+fn walk(opcodes:&[i64], assumed_written:&HashSet<usize>, assumed_writes_anywhere:bool, starts:&[usize]) -> Analysis {
+    let mut analysis = Analysis::default();
+    let mut targets = starts.to_vec();
+    let constant = |addr:usize| !assumed_writes_anywhere && !assumed_written.contains(&addr);
+
+    if assumed_writes_anywhere {
+        analysis.writes_anywhere = true;
+        analysis.modified_code = true;
+        analysis.indirect_jumps = true;
+    }
+
+    while let Some(pc) = targets.pop() {
+        if pc >= opcodes.len() || !analysis.instructions.insert(pc) {
+            continue;
+        }
+
+        if !constant(pc) {
+            analysis.modified_code = true;
+            analysis.writes_anywhere = true;
+            analysis.reads_anywhere = true;
+            analysis.indirect_jumps = true;
+            continue;
+        }
+
+        let opcode = cell(opcodes, pc);
+        let count = match params(opcode) {
+            Some(count) => count,
+            None => continue,
+        };
+
+        for par_num in 1..=count {
+            let operand = pc + par_num;
+            let addr = match mode(opcode, par_num) {
+                2 => None,
+                1 if !is_write(opcode, par_num) => continue,
+                _ if constant(operand) => Some(cell(opcodes, operand) as usize),
+                _ => None,
+            };
+
+            match (is_write(opcode, par_num), addr) {
+                (true, Some(addr)) => { analysis.written.insert(addr); },
+                (true, None) => analysis.writes_anywhere = true,
+                (false, Some(addr)) => { analysis.read.insert(addr); },
+                (false, None) => analysis.reads_anywhere = true,
+            }
+        }
+
+        match opcode % 100 {
+            99 => {},
+            5 | 6 => {
+                let value = |par_num:usize| match mode(opcode, par_num) {
+                    1 if constant(pc + par_num) => Some(cell(opcodes, pc + par_num)),
+                    0 if constant(pc + par_num) && constant(cell(opcodes, pc + par_num) as usize) =>
+                        Some(cell(opcodes, cell(opcodes, pc + par_num) as usize)),
+                    _ => None,
+                };
+
+                match value(2) {
+                    Some(target) if target >= 0 => targets.push(target as usize),
+                    Some(_) => {},
+                    None => analysis.indirect_jumps = true,
+                }
+                let always_jumps = value(1).is_some_and(|value| (value != 0) == (opcode % 100 == 5));
+                if !always_jumps {
+                    targets.push(pc + 3);
+                }
+            },
+            _ => targets.push(pc + 1 + count),
+        }
+    }
+
+    analysis
+}
+
+// Rewrites reachable instructions that are provably never modified nor read as
+// data: position reads of constant cells become immediates, and arithmetic or
+// comparisons on immediates are folded to `add result, 0`.
+pub fn optimize(opcodes:&[i64]) -> (Vec<i64>, Report) {
+    let analysis = analyse(opcodes);
+    let mut report = Report{instructions:analysis.instructions.len(),
+        writes_anywhere:analysis.writes_anywhere,
+        reads_anywhere:analysis.reads_anywhere,
+        indirect_jumps:analysis.indirect_jumps,
+        modified_code:analysis.modified_code,
+        ..Report::default()};
+    let mut optimized = opcodes.to_vec();
+
+    for pc in analysis.instructions.iter().cloned() {
+        let opcode = cell(opcodes, pc);
+        let count = match params(opcode) {
+            Some(count) => count,
+            None => continue,
+        };
+
+        let untouchable = analysis.writes_anywhere || analysis.reads_anywhere ||
+            (pc..=pc + count).any(|addr| analysis.written.contains(&addr) || analysis.read.contains(&addr));
+        if untouchable {
+            report.refused.push(pc);
+            continue;
+        }
+
+        let mut new_opcode = opcode;
+        for par_num in 1..=count {
+            let target = cell(opcodes, pc + par_num);
+            if is_write(opcode, par_num) || mode(opcode, par_num) != 0 || target < 0 || analysis.written.contains(&(target as usize)) {
+                continue;
+            }
+            new_opcode += 10_i64.pow(par_num as u32 + 1);
+            optimized[pc + par_num] = cell(opcodes, target as usize);
+            report.immediates += 1;
+        }
+        optimized[pc] = new_opcode;
+
+        let operation = new_opcode % 100;
+        let foldable = [1, 2, 7, 8].contains(&operation) && mode(new_opcode, 1) == 1 && mode(new_opcode, 2) == 1;
+        if foldable {
+            let (a, b) = (optimized[pc + 1], optimized[pc + 2]);
+            let result = match operation {
+                1 => a + b,
+                2 => a * b,
+                7 => (a < b) as i64,
+                _ => (a == b) as i64,
+            };
+            let folded = 1101 + mode(new_opcode, 3) * 10000;
+            if (optimized[pc], optimized[pc + 1], optimized[pc + 2]) != (folded, result, 0) {
+                optimized[pc] = folded;
+                optimized[pc + 1] = result;
+                optimized[pc + 2] = 0;
+                report.folded.push(pc);
+            }
+        }
+    }
+
+    // Code that may be rewritten can reach anything, so nothing is provably unreachable
+    let code_cells = analysis.code_cells(opcodes);
+    if !analysis.modified_code {
+        report.unreachable = ranges((0..opcodes.len()).filter(|addr| !code_cells.contains(addr)));
+    }
+    if !analysis.reads_anywhere {
+        let mut write_only:Vec<usize> = analysis.written.iter()
+            .filter(|addr| !analysis.read.contains(addr) && !code_cells.contains(addr))
+            .cloned()
+            .collect();
+        write_only.sort();
+        report.write_only = write_only;
+    }
+
+    // Memory past the end reads as zero, so an unused tail can go as long as all
+    // reads, writes and jumps of the rewritten program are known
+    let after = analyse(&optimized);
+    if !after.reads_anywhere && !after.writes_anywhere && !after.indirect_jumps && !after.modified_code {
+        let code_end = after.code_cells(&optimized).iter().max().map_or(0, |addr| addr + 1);
+        let read_end = after.read.iter().max().map_or(0, |addr| addr + 1);
+        let keep = code_end.max(read_end).min(optimized.len());
+        report.removed = optimized.len() - keep;
+        optimized.truncate(keep);
+    }
+
+    (optimized, report)
+}
+
+fn ranges<I:Iterator<Item=usize>>(addresses:I) -> Vec<(usize, usize)> {
+    let mut ranges:Vec<(usize, usize)> = vec![];
+    for addr in addresses {
+        match ranges.last_mut() {
+            Some((_, end)) if *end == addr => *end += 1,
+            _ => ranges.push((addr, addr + 1)),
+        }
+    }
+    ranges
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "reachable instructions: {}", self.instructions)?;
+        if self.modified_code {
+            writeln!(f, "opcodes are rewritten at runtime, the code can not be analysed")?;
+        }
+        if self.writes_anywhere {
+            writeln!(f, "writes through relative or computed addresses, nothing can be proven unmodified")?;
+        }
+        if self.reads_anywhere {
+            writeln!(f, "reads through relative or computed addresses, any cell may be data")?;
+        }
+        if self.indirect_jumps {
+            writeln!(f, "indirect jumps, constants in memory are assumed to be the only targets")?;
+        }
+        writeln!(f, "folded instructions: {:?}", self.folded)?;
+        writeln!(f, "operands made immediate: {}", self.immediates)?;
+        writeln!(f, "refused instructions: {}", self.refused.len())?;
+        writeln!(f, "unreachable regions: {:?}", self.unreachable)?;
+        writeln!(f, "write-only cells: {:?}", self.write_only)?;
+        writeln!(f, "removed trailing cells: {}", self.removed)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope.
+    use super::*;
+    use crate::util::int_code_computer::*;
+
+    fn outputs(opcodes:Vec<i64>, inputs:Vec<i64>) -> Vec<i64> {
+        let mut program = Program::new(opcodes, Some(inputs));
+        program.run();
+        program.get_output().clone()
+    }
+
+    #[test]
+    fn test_fold_and_truncate() {
+        let opcodes = vec![
+            1101,2,3,20,    // [20] = 2 + 3
+            1002,21,4,22,   // [22] = [21] * 4, [21] is never written
+            4,22,
+            4,20,
+            1,21,21,23,     // [23] = [21] + [21], never read
+            99,
+            1,1,1,          // never executed
+            0,7,0,0,0,0,0];
+        let (optimized, report) = optimize(&opcodes);
+
+        assert_eq!(optimized, vec![1101,5,0,20, 1101,28,0,22, 4,22, 4,20, 1101,14,0,23, 99, 1,1,1, 0,7,0]);
+        assert_eq!(report.folded, vec![0, 4, 12]);
+        assert_eq!(report.immediates, 3);
+        assert_eq!(report.unreachable, vec![(17, 27)]);
+        assert_eq!(report.write_only, vec![23]);
+        assert_eq!(report.removed, 4);
+        assert_eq!(outputs(optimized, vec![]), outputs(opcodes, vec![]));
+    }
+
+    #[test]
+    fn test_constant_branch() {
+        let opcodes = vec![
+            1105,1,7,       // always jumps
+            104,1,          // never executed
+            99,0,
+            104,2,
+            99];
+        let (optimized, report) = optimize(&opcodes);
+
+        assert_eq!(report.unreachable, vec![(3, 7)]);
+        assert_eq!(outputs(optimized, vec![]), vec![2]);
+    }
+
+    #[test]
+    fn test_refuses_self_modifying_code() {
+        // The first instruction rewrites the opcode of the second one into a multiply
+        let opcodes = vec![1101,1,1,4, 1,9,9,9, 99, 3];
+        let (optimized, report) = optimize(&opcodes);
+
+        assert!(report.modified_code);
+        assert!(report.refused.contains(&4));
+        assert_eq!(optimized, opcodes);
+        let mut program = Program::new(optimized, None);
+        program.run();
+        assert_eq!(program.get_memory(9), 9);
+    }
+
+    #[test]
+    fn test_relative_writes_touch_nothing() {
+        let opcodes:Vec<i64> = std::fs::read_to_string("input_09.txt").unwrap()
+            .split(',').map(|op| op.trim().parse().unwrap()).collect();
+        let (optimized, report) = optimize(&opcodes);
+
+        assert!(report.writes_anywhere);
+        assert_eq!(optimized, opcodes);
+    }
+
+    #[test]
+    fn test_relative_write_changes_jump_target() {
+        // The relative write stores 30 over the target of the jump that follows
+        let mut opcodes = vec![21101,0,30,6, 1105,1,10, 0,0,0, 99];
+        opcodes.resize(30, 0);
+        opcodes.extend_from_slice(&[104,7,99]);
+        let (optimized, report) = optimize(&opcodes);
+
+        assert!(report.writes_anywhere);
+        assert!(report.modified_code);
+        assert!(report.unreachable.is_empty());
+        assert_eq!(report.removed, 0);
+        assert_eq!(optimized, opcodes);
+        assert_eq!(outputs(optimized, vec![]), vec![7]);
+    }
+
+    #[test]
+    fn test_day_05_equivalent() {
+        let opcodes:Vec<i64> = std::fs::read_to_string("input_5.txt").unwrap()
+            .split(',').map(|op| op.trim().parse().unwrap()).collect();
+        let (optimized, report) = optimize(&opcodes);
+
+        assert!(report.modified_code, "{}", report);
+        assert_eq!(optimized, opcodes);
+        assert_eq!(outputs(optimized.clone(), vec![1]), outputs(opcodes.clone(), vec![1]));
+        assert_eq!(outputs(optimized, vec![5]), outputs(opcodes, vec![5]));
+    }
+}
@@ -0,0 +1,221 @@
+use crate::util::int_code_computer::*;
+use std::fmt;
+
+const HEADER:&str = "# intcode replay v1";
+
+// Inputs and outputs of a session, each with the number of instructions executed
+// before it was consumed or produced
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Recording {
+    pub inputs:Vec<(u64, i64)>,
+    pub outputs:Vec<(u64, i64)>,
+}
+
+impl Recording {
+    pub fn parse(text:&str) -> Result<Recording, String> {
+        let mut recording = Recording::default();
+
+        for (line_no, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let fields:Vec<&str> = line.split_whitespace().collect();
+            let parse = |field:&str| field.parse::<i64>().map_err(|_| format!("line {}: bad number '{}'", line_no + 1, field));
+            match fields.as_slice() {
+                ["in", step, value] => recording.inputs.push((parse(step)? as u64, parse(value)?)),
+                ["out", step, value] => recording.outputs.push((parse(step)? as u64, parse(value)?)),
+                _ => return Err(format!("line {}: expected 'in|out <step> <value>'", line_no + 1)),
+            }
+        }
+
+        Ok(recording)
+    }
+
+    pub fn load(path:&str) -> Result<Recording, String> {
+        let text = std::fs::read_to_string(path).map_err(|err| format!("{}: {}", path, err))?;
+        Recording::parse(&text)
+    }
+
+    pub fn save(&self, path:&str) -> Result<(), String> {
+        std::fs::write(path, self.to_string()).map_err(|err| format!("{}: {}", path, err))
+    }
+}
+
+impl fmt::Display for Recording {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}", HEADER)?;
+        let mut events:Vec<(u64, &str, i64)> = self.inputs.iter().map(|(step, value)| (*step, "in", *value))
+            .chain(self.outputs.iter().map(|(step, value)| (*step, "out", *value)))
+            .collect();
+        events.sort_by_key(|(step, _, _)| *step);
+
+        for (step, kind, value) in events {
+            writeln!(f, "{} {} {}", kind, step, value)?;
+        }
+        Ok(())
+    }
+}
+
+// Observer building a recording of a live session
+#[derive(Default)]
+pub struct Recorder {
+    pub recording:Recording,
+    steps:u64,
+}
+
+impl Recorder {
+    pub fn new() -> Recorder {
+        Recorder::default()
+    }
+}
+
+impl Observer for Recorder {
+    fn instruction_executed(&mut self, _pc:usize, _opcode:i64) {
+        self.steps += 1;
+    }
+
+    fn input_consumed(&mut self, _pc:usize, value:i64) {
+        self.recording.inputs.push((self.steps - 1, value));
+    }
+
+    fn output_produced(&mut self, _pc:usize, value:i64) {
+        self.recording.outputs.push((self.steps - 1, value));
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Divergence {
+    // The n:th input was consumed at a different instruction count
+    InputStep{index:usize, expected:u64, actual:u64},
+    // The program wanted more input than was recorded
+    InputMissing{index:usize, step:u64},
+    // The n:th output differs, None when one side has no such output
+    Output{index:usize, expected:Option<(u64, i64)>, actual:Option<(u64, i64)>},
+    Error(String),
+}
+
+// Feeds the recorded inputs to a fresh program whenever it asks for one and
+// compares the output stream, stopping at the first divergence.
+pub fn replay(opcodes:Vec<i64>, recording:&Recording) -> Result<Vec<i64>, Divergence> {
+    let mut program = Program::new(opcodes, None);
+    let mut steps:u64 = 0;
+    let mut next_input = 0;
+    let mut outputs = vec![];
+
+    loop {
+        let before = program.get_output().len();
+        match program.step() {
+            None => steps += 1,
+            Some(StopReason::NeedsInput) => {
+                match recording.inputs.get(next_input) {
+                    Some((step, value)) => {
+                        if *step != steps {
+                            return Err(Divergence::InputStep{index:next_input, expected:*step, actual:steps});
+                        }
+                        program.add_input(*value);
+                        next_input += 1;
+                    },
+                    None => return Err(Divergence::InputMissing{index:next_input, step:steps}),
+                }
+                continue;
+            },
+            Some(StopReason::Halted) => break,
+            Some(StopReason::Error(message)) => return Err(Divergence::Error(message)),
+            Some(StopReason::Breakpoint(_)) => continue,
+        }
+
+        if program.get_output().len() > before {
+            let actual = (steps - 1, *program.get_output().last().unwrap());
+            let expected = recording.outputs.get(outputs.len()).cloned();
+            if expected != Some(actual) {
+                return Err(Divergence::Output{index:outputs.len(), expected, actual:Some(actual)});
+            }
+            outputs.push(actual.1);
+        }
+    }
+
+    if outputs.len() < recording.outputs.len() {
+        return Err(Divergence::Output{index:outputs.len(), expected:Some(recording.outputs[outputs.len()]), actual:None});
+    }
+
+    Ok(outputs)
+}
+
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope.
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    // Reads numbers and outputs them doubled until a zero is read
+    const PROGRAM:[i64;16] = [3,15, 1006,15,14, 1002,15,2,15, 4,15, 1105,1,0, 99, 0];
+
+    fn record(inputs:&[i64]) -> Recording {
+        let recorder = Arc::new(Mutex::new(Recorder::new()));
+        let mut program = Program::new(PROGRAM.to_vec(), None);
+        program.add_observer(recorder.clone());
+
+        // Interactive session, inputs are only provided once asked for
+        let mut inputs = inputs.iter();
+        while !program.is_halted() {
+            if program.needs_input() {
+                program.add_input(*inputs.next().unwrap());
+            }
+            program.run_instruction();
+        }
+
+        let recording = recorder.lock().unwrap().recording.clone();
+        recording
+    }
+
+    #[test]
+    fn test_record_and_replay() {
+        let recording = record(&[3, 5, 0]);
+        assert_eq!(recording.inputs, vec![(0, 3), (5, 5), (10, 0)]);
+        assert_eq!(recording.outputs, vec![(3, 6), (8, 10)]);
+
+        let text = recording.to_string();
+        assert_eq!(text, "# intcode replay v1\nin 0 3\nout 3 6\nin 5 5\nout 8 10\nin 10 0\n");
+        assert_eq!(Recording::parse(&text), Ok(recording.clone()));
+
+        assert_eq!(replay(PROGRAM.to_vec(), &recording), Ok(vec![6, 10]));
+    }
+
+    #[test]
+    fn test_replay_divergence() {
+        let mut recording = record(&[3, 5, 0]);
+        recording.outputs[1].1 = 11;
+        assert_eq!(replay(PROGRAM.to_vec(), &recording),
+                   Err(Divergence::Output{index:1, expected:Some((8, 11)), actual:Some((8, 10))}));
+
+        let mut recording = record(&[3, 5, 0]);
+        recording.inputs[1].0 = 7;
+        assert_eq!(replay(PROGRAM.to_vec(), &recording), Err(Divergence::InputStep{index:1, expected:7, actual:5}));
+
+        let mut recording = record(&[3, 5, 0]);
+        recording.inputs.pop();
+        assert_eq!(replay(PROGRAM.to_vec(), &recording), Err(Divergence::InputMissing{index:2, step:10}));
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let recording = record(&[7, 0]);
+        // Unique per process so that concurrent test runs do not share the file
+        let path = std::env::temp_dir().join(format!("intcode_replay_test_{}.txt", std::process::id()));
+        let path = path.to_str().unwrap();
+
+        recording.save(path).unwrap();
+        let loaded = Recording::load(path);
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(loaded, Ok(recording));
+    }
+
+    #[test]
+    fn test_parse_error() {
+        assert!(Recording::parse("in 1\n").is_err());
+        assert!(Recording::parse("out x 2\n").is_err());
+    }
+}
@@ -0,0 +1,212 @@
+use crate::util::int_code_computer::*;
+use std::collections::VecDeque;
+use std::fmt;
+
+pub type Pid = usize;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum State {
+    Ready,
+    // Parked until input is sent to it
+    Blocked,
+    Halted,
+    Failed(String),
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Stats {
+    pub instructions:u64,
+    pub slices:u64,
+    // Number of times the program was parked waiting for input
+    pub parked:u64,
+    pub inputs:u64,
+    pub outputs:u64,
+}
+
+struct Task {
+    program:Program,
+    state:State,
+    stats:Stats,
+}
+
+// Runs a set of programs round robin, each for at most `quantum` instructions at
+// a time. Programs waiting for input are left out until input is sent to them.
+pub struct Scheduler {
+    tasks:Vec<Task>,
+    ready:VecDeque<Pid>,
+    quantum:u64,
+}
+
+impl Scheduler {
+    pub fn new(quantum:u64) -> Scheduler {
+        Scheduler{tasks:vec![], ready:VecDeque::new(), quantum:quantum.max(1)}
+    }
+
+    pub fn spawn(&mut self, program:Program) -> Pid {
+        let pid = self.tasks.len();
+        self.tasks.push(Task{program, state:State::Ready, stats:Stats::default()});
+        self.ready.push_back(pid);
+        pid
+    }
+
+    // Queues an input value, waking the program if it was parked
+    pub fn send(&mut self, pid:Pid, value:i64) {
+        let task = &mut self.tasks[pid];
+        task.program.add_input(value);
+        task.stats.inputs += 1;
+        if task.state == State::Blocked {
+            task.state = State::Ready;
+            self.ready.push_back(pid);
+        }
+    }
+
+    // Runs the next ready program for one quantum and returns it along with the
+    // outputs it produced, or None once no program is ready
+    pub fn run_slice(&mut self) -> Option<(Pid, Vec<i64>)> {
+        let pid = self.ready.pop_front()?;
+        let task = &mut self.tasks[pid];
+        task.stats.slices += 1;
+
+        let mut executed = 0;
+        task.state = loop {
+            if executed == self.quantum {
+                break State::Ready;
+            }
+            match task.program.step() {
+                None => executed += 1,
+                Some(StopReason::NeedsInput) => break State::Blocked,
+                Some(StopReason::Halted) => break State::Halted,
+                Some(StopReason::Error(message)) => break State::Failed(message),
+                Some(StopReason::Breakpoint(_)) => {},
+            }
+        };
+
+        task.stats.instructions += executed;
+        match task.state {
+            State::Ready => self.ready.push_back(pid),
+            State::Blocked => task.stats.parked += 1,
+            _ => {},
+        }
+
+        let outputs = task.program.take_output();
+        task.stats.outputs += outputs.len() as u64;
+        Some((pid, outputs))
+    }
+
+    // Runs until every program is parked, halted or failed, passing all outputs
+    // to the callback
+    pub fn run_until_idle<F:FnMut(&mut Scheduler, Pid, Vec<i64>)>(&mut self, mut on_output:F) {
+        while let Some((pid, outputs)) = self.run_slice() {
+            if !outputs.is_empty() {
+                on_output(self, pid, outputs);
+            }
+        }
+    }
+
+    pub fn is_idle(&self) -> bool {
+        self.ready.is_empty()
+    }
+
+    pub fn blocked(&self) -> Vec<Pid> {
+        (0..self.tasks.len()).filter(|pid| self.tasks[*pid].state == State::Blocked).collect()
+    }
+
+    pub fn state(&self, pid:Pid) -> &State {
+        &self.tasks[pid].state
+    }
+
+    pub fn stats(&self, pid:Pid) -> &Stats {
+        &self.tasks[pid].stats
+    }
+
+    pub fn program(&self, pid:Pid) -> &Program {
+        &self.tasks[pid].program
+    }
+
+    pub fn len(&self) -> usize {
+        self.tasks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tasks.is_empty()
+    }
+}
+
+impl fmt::Display for Scheduler {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{:>5} {:>8} {:>12} {:>8} {:>8} {:>8} {:>8}", "pid", "state", "instructions", "slices", "parked", "inputs", "outputs")?;
+        for (pid, task) in self.tasks.iter().enumerate() {
+            let state = match task.state {
+                State::Ready => "ready",
+                State::Blocked => "blocked",
+                State::Halted => "halted",
+                State::Failed(_) => "failed",
+            };
+            writeln!(f, "{:>5} {:>8} {:>12} {:>8} {:>8} {:>8} {:>8}", pid, state, task.stats.instructions,
+                     task.stats.slices, task.stats.parked, task.stats.inputs, task.stats.outputs)?;
+        }
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope.
+    use super::*;
+
+    // Outputs double of every input until a zero is read
+    const DOUBLER:[i64;16] = [3,15, 1006,15,14, 1002,15,2,15, 4,15, 1105,1,0, 99, 0];
+
+    #[test]
+    fn test_park_and_wake() {
+        let mut scheduler = Scheduler::new(100);
+        let pid = scheduler.spawn(Program::new(DOUBLER.to_vec(), None));
+
+        assert_eq!(scheduler.run_slice(), Some((pid, vec![])));
+        assert_eq!(scheduler.state(pid), &State::Blocked);
+        assert!(scheduler.is_idle());
+        assert_eq!(scheduler.run_slice(), None);
+
+        scheduler.send(pid, 4);
+        scheduler.send(pid, 5);
+        assert_eq!(scheduler.run_slice(), Some((pid, vec![8, 10])));
+        assert_eq!(scheduler.state(pid), &State::Blocked);
+
+        scheduler.send(pid, 0);
+        assert_eq!(scheduler.run_slice(), Some((pid, vec![])));
+        assert_eq!(scheduler.state(pid), &State::Halted);
+
+        assert_eq!(scheduler.stats(pid), &Stats{instructions:12, slices:3, parked:2, inputs:3, outputs:2});
+    }
+
+    #[test]
+    fn test_quantum_interleaves() {
+        // Counts mem[12] down from 3, outputting each value
+        let counter = vec![4,12, 1001,12,-1,12, 1005,12,0, 99, 0, 0, 3];
+        let mut scheduler = Scheduler::new(2);
+        let first = scheduler.spawn(Program::new(counter.clone(), None));
+        let second = scheduler.spawn(Program::new(counter, None));
+
+        let mut order = vec![];
+        scheduler.run_until_idle(|_, pid, outputs| order.extend(outputs.iter().map(|value| (pid, *value))));
+
+        assert_eq!(order, vec![(first, 3), (second, 3), (first, 2), (second, 2), (first, 1), (second, 1)]);
+        assert_eq!(scheduler.stats(first).instructions, 9);
+        assert_eq!(scheduler.stats(first).slices, 5);
+        assert_eq!(scheduler.blocked(), vec![]);
+    }
+
+    #[test]
+    fn test_failure() {
+        let mut scheduler = Scheduler::new(10);
+        let pid = scheduler.spawn(Program::new(vec![1101,1,1,5, 77], None));
+        scheduler.run_until_idle(|_, _, _| {});
+
+        match scheduler.state(pid) {
+            State::Failed(message) => assert!(message.contains("77")),
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(scheduler.to_string().contains("failed"));
+    }
+}
@@ -0,0 +1,162 @@
+use crate::util::int_code_computer::*;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Objective {
+    // Lowest indexed candidate with a score, stops as soon as it is known
+    First,
+    Max,
+    Min,
+    Sum,
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchStats {
+    pub evaluated:usize,
+    pub threads:usize,
+    pub elapsed:Duration,
+}
+
+impl SearchStats {
+    pub fn per_second(&self) -> f64 {
+        self.evaluated as f64 / self.elapsed.as_secs_f64().max(1e-9)
+    }
+}
+
+#[derive(Debug)]
+pub struct SearchResult<I> {
+    // Winning candidate and its score for First, Max and Min
+    pub best:Option<(I, i64)>,
+    pub sum:i64,
+    pub stats:SearchStats,
+}
+
+// Index, candidate and score of the best candidate seen by a worker
+type Best<I> = Option<(usize, I, i64)>;
+
+pub fn default_threads() -> usize {
+    std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1)
+}
+
+// Scores every candidate of the input space on a pool of worker threads. The
+// score function returns None for candidates that do not match.
+pub fn search<I, S, F>(space:S, objective:Objective, threads:usize, score:F) -> SearchResult<I>
+    where I:Send + Clone, S:IntoIterator<Item=I>, S::IntoIter:Send, F:Fn(&I) -> Option<i64> + Sync {
+    let start = Instant::now();
+    let candidates = Mutex::new(space.into_iter().enumerate());
+    let first_found = AtomicUsize::new(usize::MAX);
+    let evaluated = AtomicUsize::new(0);
+    let threads = threads.max(1);
+
+    let results:Vec<(Best<I>, i64)> = std::thread::scope(|scope| {
+        let workers:Vec<_> = (0..threads).map(|_| scope.spawn(|| {
+            let mut best:Best<I> = None;
+            let mut sum = 0;
+            loop {
+                let next = candidates.lock().unwrap().next();
+                let (index, candidate) = match next {
+                    Some(next) => next,
+                    None => break,
+                };
+                if objective == Objective::First && index > first_found.load(Ordering::Relaxed) {
+                    break;
+                }
+
+                evaluated.fetch_add(1, Ordering::Relaxed);
+                let value = match score(&candidate) {
+                    Some(value) => value,
+                    None => continue,
+                };
+
+                sum += value;
+                let better = match (&best, objective) {
+                    (None, _) => true,
+                    (Some((i, _, _)), Objective::First) => index < *i,
+                    (Some((_, _, v)), Objective::Max) => value > *v,
+                    (Some((_, _, v)), Objective::Min) => value < *v,
+                    (Some(_), Objective::Sum) => false,
+                };
+                if better {
+                    best = Some((index, candidate, value));
+                }
+                if objective == Objective::First {
+                    first_found.fetch_min(index, Ordering::Relaxed);
+                }
+            }
+            (best, sum)
+        })).collect();
+
+        workers.into_iter().map(|worker| worker.join().unwrap()).collect()
+    });
+
+    let sum = results.iter().map(|(_, sum)| sum).sum();
+    let best = results.into_iter()
+        .filter_map(|(best, _)| best)
+        .fold(None, |acc:Best<I>, (index, candidate, value)| {
+            let better = match &acc {
+                None => true,
+                Some((i, _, v)) => match objective {
+                    Objective::Max => value > *v || (value == *v && index < *i),
+                    Objective::Min => value < *v || (value == *v && index < *i),
+                    _ => index < *i,
+                },
+            };
+            if better { Some((index, candidate, value)) } else { acc }
+        })
+        .map(|(_, candidate, value)| (candidate, value));
+
+    let stats = SearchStats{evaluated:evaluated.into_inner(), threads, elapsed:start.elapsed()};
+    SearchResult{best, sum, stats}
+}
+
+// Same as search, but hands every evaluation its own clone of the program
+pub fn search_program<I, S, F>(template:&Program, space:S, objective:Objective, threads:usize, score:F) -> SearchResult<I>
+    where I:Send + Clone, S:IntoIterator<Item=I>, S::IntoIter:Send, F:Fn(&mut Program, &I) -> Option<i64> + Sync {
+    search(space, objective, threads, |candidate| {
+        let mut program = template.clone();
+        score(&mut program, candidate)
+    })
+}
+
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope.
+    use super::*;
+
+    #[test]
+    fn test_first_is_lowest_index() {
+        let result = search(0..10_000, Objective::First, 4, |n| if n % 7 == 3 && *n > 50 { Some(*n) } else { None });
+        assert_eq!(result.best, Some((52, 52)));
+        assert!(result.stats.evaluated < 10_000);
+    }
+
+    #[test]
+    fn test_max_min_sum() {
+        let space:Vec<i64> = (-20..=20).collect();
+        let square = |n:&i64| Some(n * n - 4 * n);
+
+        assert_eq!(search(space.clone(), Objective::Max, 3, square).best, Some((-20, 480)));
+        assert_eq!(search(space.clone(), Objective::Min, 3, square).best, Some((2, -4)));
+
+        let result = search(space, Objective::Sum, 3, |n| if *n > 0 { Some(*n) } else { None });
+        assert_eq!(result.sum, 210);
+        assert_eq!(result.stats.evaluated, 41);
+    }
+
+    #[test]
+    fn test_search_program() {
+        // Outputs input * 3
+        let template = Program::new(vec![3,9,1002,9,3,9,4,9,99,0], None);
+        let result = search_program(&template, 0..100, Objective::First, 2, |program, n| {
+            program.add_input(*n);
+            match program.run_until_output(1)[0] {
+                150 => Some(150),
+                _ => None,
+            }
+        });
+        assert_eq!(result.best, Some((50, 150)));
+    }
+}
@@ -0,0 +1,448 @@
+use crate::util::int_code_computer::*;
+use std::collections::{BTreeMap, HashMap};
+
+// Where a symbol is injected into the program
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Source {
+    Memory(usize),
+    Input(usize),
+}
+
+// A symbol with an inclusive range of values it may take
+#[derive(Debug, Clone, PartialEq)]
+pub struct Symbol {
+    pub source:Source,
+    pub min:i64,
+    pub max:i64,
+}
+
+impl Symbol {
+    pub fn new(source:Source, min:i64, max:i64) -> Symbol {
+        Symbol{source, min, max}
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Target {
+    // Memory cell holds the value once the program halts
+    Memory(usize, i64),
+    // The n:th output equals the value
+    Output(usize, i64),
+}
+
+// constant + sum(coefficient * symbol)
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinearExpr {
+    pub constant:i64,
+    pub terms:BTreeMap<usize, i64>,
+}
+
+impl LinearExpr {
+    pub fn constant(value:i64) -> LinearExpr {
+        LinearExpr{constant:value, terms:BTreeMap::new()}
+    }
+
+    pub fn symbol(id:usize) -> LinearExpr {
+        let mut terms = BTreeMap::new();
+        terms.insert(id, 1);
+        LinearExpr{constant:0, terms}
+    }
+
+    pub fn as_constant(&self) -> Option<i64> {
+        match self.terms.is_empty() {
+            true => Some(self.constant),
+            false => None,
+        }
+    }
+
+    pub fn coefficient(&self, id:usize) -> i64 {
+        *self.terms.get(&id).unwrap_or(&0)
+    }
+
+    pub fn eval(&self, values:&[i64]) -> i64 {
+        self.terms.iter().fold(self.constant, |sum, (id, factor)| sum + factor * values[*id])
+    }
+
+    fn add(&self, other:&LinearExpr) -> LinearExpr {
+        let mut terms = self.terms.clone();
+        for (id, factor) in other.terms.iter() {
+            *terms.entry(*id).or_insert(0) += factor;
+        }
+        terms.retain(|_, factor| *factor != 0);
+        LinearExpr{constant:self.constant + other.constant, terms}
+    }
+
+    fn scale(&self, factor:i64) -> LinearExpr {
+        let mut terms:BTreeMap<usize, i64> = self.terms.iter().map(|(id, f)| (*id, f * factor)).collect();
+        terms.retain(|_, factor| *factor != 0);
+        LinearExpr{constant:self.constant * factor, terms}
+    }
+}
+
+// Opaque values are derived from symbols in a way that is not linear
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Linear(LinearExpr),
+    Opaque,
+}
+
+impl Value {
+    fn concrete(&self) -> Option<i64> {
+        match self {
+            Value::Linear(expr) => expr.as_constant(),
+            Value::Opaque => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Abort {
+    // Control flow, an address or the relative base depends on a symbol
+    SymbolicControl(usize),
+    UnknownOpcode(usize),
+    InputMissing(usize),
+    StepLimit,
+}
+
+pub struct SymbolicState {
+    pub memory:HashMap<usize, Value>,
+    pub outputs:Vec<Value>,
+}
+
+impl SymbolicState {
+    pub fn get_memory(&self, addr:usize) -> Value {
+        self.memory.get(&addr).cloned().unwrap_or_else(|| Value::Linear(LinearExpr::constant(0)))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Method {
+    Symbolic,
+    Search,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Solution {
+    pub values:Vec<i64>,
+    pub method:Method,
+}
+
+struct Executor {
+    pc:usize,
+    relative_base:i64,
+    memory:HashMap<usize, Value>,
+    inputs:Vec<Value>,
+    outputs:Vec<Value>,
+}
+
+impl Executor {
+    fn get(&self, addr:usize) -> Value {
+        self.memory.get(&addr).cloned().unwrap_or_else(|| Value::Linear(LinearExpr::constant(0)))
+    }
+
+    fn concrete(&self, addr:usize) -> Result<i64, Abort> {
+        self.get(addr).concrete().ok_or(Abort::SymbolicControl(self.pc))
+    }
+
+    fn mode(&self, opcode:i64, par_num:u32) -> i64 {
+        opcode / 10_i64.pow(par_num + 1) % 10
+    }
+
+    fn param_value(&self, opcode:i64, par_num:u32) -> Result<Value, Abort> {
+        let raw = self.get(self.pc + par_num as usize);
+        if self.mode(opcode, par_num) == 1 {
+            return Ok(raw);
+        }
+
+        // Reading through a symbolic pointer gives an unknown but harmless value
+        let offset = if self.mode(opcode, par_num) == 2 { self.relative_base } else { 0 };
+        match raw.concrete() {
+            Some(addr) => Ok(self.get((addr + offset) as usize)),
+            None => Ok(Value::Opaque),
+        }
+    }
+
+    fn param_address(&self, opcode:i64, par_num:u32) -> Result<usize, Abort> {
+        let addr = self.concrete(self.pc + par_num as usize)?;
+        match self.mode(opcode, par_num) {
+            2 => Ok((addr + self.relative_base) as usize),
+            _ => Ok(addr as usize),
+        }
+    }
+
+    fn step(&mut self) -> Result<bool, Abort> {
+        let opcode = self.concrete(self.pc)?;
+        match opcode % 100 {
+            1 | 2 | 7 | 8 => {
+                let a = self.param_value(opcode, 1)?;
+                let b = self.param_value(opcode, 2)?;
+                let dest = self.param_address(opcode, 3)?;
+                let result = match (opcode % 100, &a, &b) {
+                    (1, Value::Linear(x), Value::Linear(y)) => Value::Linear(x.add(y)),
+                    (2, Value::Linear(x), Value::Linear(y)) => {
+                        match (x.as_constant(), y.as_constant()) {
+                            (Some(factor), _) => Value::Linear(y.scale(factor)),
+                            (_, Some(factor)) => Value::Linear(x.scale(factor)),
+                            _ => Value::Opaque,
+                        }
+                    },
+                    (7, _, _) | (8, _, _) => {
+                        match (a.concrete(), b.concrete()) {
+                            (Some(x), Some(y)) if opcode % 100 == 7 => Value::Linear(LinearExpr::constant((x < y) as i64)),
+                            (Some(x), Some(y)) => Value::Linear(LinearExpr::constant((x == y) as i64)),
+                            _ => Value::Opaque,
+                        }
+                    },
+                    _ => Value::Opaque,
+                };
+                self.memory.insert(dest, result);
+                self.pc += 4;
+            },
+            3 => {
+                let dest = self.param_address(opcode, 1)?;
+                if self.inputs.is_empty() {
+                    return Err(Abort::InputMissing(self.pc));
+                }
+                let value = self.inputs.remove(0);
+                self.memory.insert(dest, value);
+                self.pc += 2;
+            },
+            4 => {
+                let value = self.param_value(opcode, 1)?;
+                self.outputs.push(value);
+                self.pc += 2;
+            },
+            5 | 6 => {
+                let cond = self.param_value(opcode, 1)?.concrete().ok_or(Abort::SymbolicControl(self.pc))?;
+                let target = self.param_value(opcode, 2)?.concrete().ok_or(Abort::SymbolicControl(self.pc))?;
+                if (cond != 0) == (opcode % 100 == 5) {
+                    self.pc = target as usize;
+                } else {
+                    self.pc += 3;
+                }
+            },
+            9 => {
+                let delta = self.param_value(opcode, 1)?.concrete().ok_or(Abort::SymbolicControl(self.pc))?;
+                self.relative_base += delta;
+                self.pc += 2;
+            },
+            99 => return Ok(false),
+            _ => return Err(Abort::UnknownOpcode(self.pc)),
+        }
+
+        Ok(true)
+    }
+}
+
+// Runs the program with the given symbols in place of memory cells or inputs.
+// Concrete inputs fill every input position not taken by a symbol.
+pub fn execute(opcodes:&[i64], inputs:&[i64], symbols:&[Symbol], max_steps:usize) -> Result<SymbolicState, Abort> {
+    let mut memory:HashMap<usize, Value> = opcodes.iter().enumerate()
+        .map(|(addr, value)| (addr, Value::Linear(LinearExpr::constant(*value))))
+        .collect();
+    let mut input_values:Vec<Value> = inputs.iter().map(|value| Value::Linear(LinearExpr::constant(*value))).collect();
+
+    for (id, symbol) in symbols.iter().enumerate() {
+        match symbol.source {
+            Source::Memory(addr) => {
+                memory.insert(addr, Value::Linear(LinearExpr::symbol(id)));
+            },
+            Source::Input(index) => {
+                if input_values.len() <= index {
+                    input_values.resize(index + 1, Value::Linear(LinearExpr::constant(0)));
+                }
+                input_values[index] = Value::Linear(LinearExpr::symbol(id));
+            },
+        }
+    }
+
+    let mut executor = Executor{pc:0, relative_base:0, memory, inputs:input_values, outputs:vec![]};
+    let mut steps = 0;
+    while executor.step()? {
+        steps += 1;
+        if steps >= max_steps {
+            return Err(Abort::StepLimit);
+        }
+    }
+
+    Ok(SymbolicState{memory:executor.memory, outputs:executor.outputs})
+}
+
+// Finds symbol values making the target hold. Linear targets are solved directly,
+// anything else falls back to searching the symbol domains with the real VM.
+pub fn find_inputs(opcodes:&[i64], inputs:&[i64], symbols:&[Symbol], target:Target, max_steps:usize) -> Option<Solution> {
+    let expr = execute(opcodes, inputs, symbols, max_steps).ok().and_then(|state| {
+        let value = match target {
+            Target::Memory(addr, _) => state.get_memory(addr),
+            Target::Output(index, _) => state.outputs.get(index).cloned().unwrap_or(Value::Opaque),
+        };
+        match value {
+            Value::Linear(expr) => Some(expr),
+            Value::Opaque => None,
+        }
+    });
+
+    match expr {
+        Some(expr) => solve_linear(&expr, symbols, target_value(target))
+            .map(|values| Solution{values, method:Method::Symbolic}),
+        None => search(opcodes, inputs, symbols, target, max_steps)
+            .map(|values| Solution{values, method:Method::Search}),
+    }
+}
+
+fn target_value(target:Target) -> i64 {
+    match target {
+        Target::Memory(_, value) | Target::Output(_, value) => value,
+    }
+}
+
+// Enumerates every symbol but the last one with a non-zero coefficient in domain
+// order, and solves for that one. This gives the same first solution as a search.
+fn solve_linear(expr:&LinearExpr, symbols:&[Symbol], target:i64) -> Option<Vec<i64>> {
+    let pivot = match (0..symbols.len()).rev().find(|id| expr.coefficient(*id) != 0) {
+        Some(pivot) => pivot,
+        None => {
+            return match expr.constant == target {
+                true => Some(symbols.iter().map(|symbol| symbol.min).collect()),
+                false => None,
+            };
+        }
+    };
+
+    let mut values:Vec<i64> = symbols.iter().map(|symbol| symbol.min).collect();
+    loop {
+        values[pivot] = 0;
+        let rest = target - expr.eval(&values);
+        let factor = expr.coefficient(pivot);
+        if rest % factor == 0 {
+            let value = rest / factor;
+            if value >= symbols[pivot].min && value <= symbols[pivot].max {
+                values[pivot] = value;
+                return Some(values);
+            }
+        }
+
+        if !next_values(&mut values, symbols, pivot) {
+            return None;
+        }
+    }
+}
+
+// Advances the free symbols (those before the pivot) like an odometer
+fn next_values(values:&mut [i64], symbols:&[Symbol], pivot:usize) -> bool {
+    for id in (0..pivot).rev() {
+        if values[id] < symbols[id].max {
+            values[id] += 1;
+            return true;
+        }
+        values[id] = symbols[id].min;
+    }
+    false
+}
+
+fn search(opcodes:&[i64], inputs:&[i64], symbols:&[Symbol], target:Target, max_steps:usize) -> Option<Vec<i64>> {
+    let mut values:Vec<i64> = symbols.iter().map(|symbol| symbol.min).collect();
+    loop {
+        if check(opcodes, inputs, symbols, &values, target, max_steps) {
+            return Some(values);
+        }
+        if !next_values(&mut values, symbols, symbols.len()) {
+            return None;
+        }
+    }
+}
+
+fn check(opcodes:&[i64], inputs:&[i64], symbols:&[Symbol], values:&[i64], target:Target, max_steps:usize) -> bool {
+    let mut opcodes = opcodes.to_vec();
+    let mut inputs = inputs.to_vec();
+    for (symbol, value) in symbols.iter().zip(values.iter()) {
+        match symbol.source {
+            Source::Memory(addr) => {
+                if opcodes.len() <= addr {
+                    opcodes.resize(addr + 1, 0);
+                }
+                opcodes[addr] = *value;
+            },
+            Source::Input(index) => {
+                if inputs.len() <= index {
+                    inputs.resize(index + 1, 0);
+                }
+                inputs[index] = *value;
+            },
+        }
+    }
+
+    let mut program = Program::new(opcodes, Some(inputs));
+    let mut steps = 0;
+    while !program.is_halted() {
+        if steps >= max_steps || program.is_blocked() {
+            return false;
+        }
+        program.run_instruction();
+        steps += 1;
+    }
+
+    match target {
+        Target::Memory(addr, value) => program.get_memory(addr) == value,
+        Target::Output(index, value) => program.get_output().get(index) == Some(&value),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope.
+    use super::*;
+
+    #[test]
+    fn test_linear_memory() {
+        // mem[13] = 3 * mem[14] + mem[15] + 5
+        let opcodes = vec![1002,14,3,13,1,13,15,13,1001,13,5,13,99,0,0,0];
+        let symbols = vec![Symbol::new(Source::Memory(14), 0, 99), Symbol::new(Source::Memory(15), 0, 99)];
+
+        let state = execute(&opcodes, &[], &symbols, 100).unwrap();
+        match state.get_memory(13) {
+            Value::Linear(expr) => {
+                assert_eq!(expr.constant, 5);
+                assert_eq!(expr.coefficient(0), 3);
+                assert_eq!(expr.coefficient(1), 1);
+            },
+            Value::Opaque => panic!("expected linear value"),
+        }
+
+        let solution = find_inputs(&opcodes, &[], &symbols, Target::Memory(13, 100), 100).unwrap();
+        assert_eq!(solution, Solution{values:vec![0, 95], method:Method::Symbolic});
+    }
+
+    #[test]
+    fn test_solve_input_output() {
+        // Outputs 2 * input + 7
+        let opcodes = vec![3,13,1002,13,2,13,1001,13,7,13,4,13,99,0];
+        let symbols = vec![Symbol::new(Source::Input(0), -100, 100)];
+
+        let solution = find_inputs(&opcodes, &[], &symbols, Target::Output(0, 31), 100).unwrap();
+        assert_eq!(solution, Solution{values:vec![12], method:Method::Symbolic});
+        assert!(find_inputs(&opcodes, &[], &symbols, Target::Output(0, 30), 100).is_none());
+    }
+
+    #[test]
+    fn test_non_linear_falls_back() {
+        // mem[7] = input * input
+        let opcodes = vec![3,7,2,7,7,7,99,0];
+        let symbols = vec![Symbol::new(Source::Input(0), 0, 20)];
+
+        let solution = find_inputs(&opcodes, &[], &symbols, Target::Memory(7, 144), 100).unwrap();
+        assert_eq!(solution, Solution{values:vec![12], method:Method::Search});
+    }
+
+    #[test]
+    fn test_symbolic_jump_falls_back() {
+        // Outputs 0 unless input is less than 5
+        let opcodes = vec![3,12,1007,12,5,13,1005,13,11,104,0,99,0,0];
+        let symbols = vec![Symbol::new(Source::Input(0), 0, 10)];
+
+        assert_eq!(execute(&opcodes, &[], &symbols, 100).err(), Some(Abort::SymbolicControl(6)));
+        let solution = find_inputs(&opcodes, &[], &symbols, Target::Output(0, 0), 100).unwrap();
+        assert_eq!(solution, Solution{values:vec![5], method:Method::Search});
+    }
+}
@@ -0,0 +1,172 @@
+use std::fmt;
+use std::sync::RwLock;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Off,
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl Level {
+    pub fn parse(text:&str) -> Option<Level> {
+        match text.to_ascii_lowercase().as_str() {
+            "off" => Some(Level::Off),
+            "error" => Some(Level::Error),
+            "warn" => Some(Level::Warn),
+            "info" => Some(Level::Info),
+            "debug" => Some(Level::Debug),
+            "trace" => Some(Level::Trace),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        f.pad(match self {
+            Level::Off => "OFF",
+            Level::Error => "ERROR",
+            Level::Warn => "WARN",
+            Level::Info => "INFO",
+            Level::Debug => "DEBUG",
+            Level::Trace => "TRACE",
+        })
+    }
+}
+
+// The most verbose level shown, overridden per target. A target is the last
+// part of the module path, such as `day_13`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Filter {
+    pub level:Level,
+    pub targets:Vec<(String, Level)>,
+}
+
+impl Filter {
+    pub const fn new(level:Level) -> Filter {
+        Filter{level, targets:vec![]}
+    }
+
+    // Parses `info,day_13=trace,day_10=off`. A bare level sets the default.
+    pub fn parse(spec:&str, level:Level) -> Result<Filter, String> {
+        let mut filter = Filter::new(level);
+        for item in spec.split(',').map(str::trim).filter(|item| !item.is_empty()) {
+            match item.split_once('=') {
+                Some((target, level)) => {
+                    let level = Level::parse(level).ok_or_else(|| format!("unknown log level '{}'", level))?;
+                    filter.targets.push((normalize_target(target), level));
+                },
+                None => filter.level = Level::parse(item).ok_or_else(|| format!("unknown log level '{}'", item))?,
+            }
+        }
+        Ok(filter)
+    }
+
+    pub fn enabled(&self, target:&str, level:Level) -> bool {
+        let max = self.targets.iter().rev()
+            .find(|(name, _)| name == target)
+            .map_or(self.level, |(_, level)| *level);
+        level != Level::Off && level <= max
+    }
+}
+
+// Accepts `day13` as well as `day_13`
+fn normalize_target(target:&str) -> String {
+    match target.strip_prefix("day").and_then(|day| day.trim_start_matches('_').parse::<u8>().ok()) {
+        Some(day) => format!("day_{:02}", day),
+        None => String::from(target),
+    }
+}
+
+// Warnings and errors show until the binary or a test sets a filter
+static FILTER:RwLock<Filter> = RwLock::new(Filter::new(Level::Warn));
+
+pub fn set_filter(filter:Filter) {
+    *FILTER.write().unwrap() = filter;
+}
+
+pub fn enabled(module_path:&str, level:Level) -> bool {
+    FILTER.read().unwrap().enabled(target(module_path), level)
+}
+
+pub fn target(module_path:&str) -> &str {
+    module_path.rsplit("::").next().unwrap_or(module_path)
+}
+
+// Messages go to stderr so that they never mix with answers
+pub fn write(module_path:&str, level:Level, message:fmt::Arguments) {
+    eprintln!("[{:<5} {}] {}", level, target(module_path), message);
+}
+
+macro_rules! log {
+    ($level:expr, $($arg:tt)+) => {
+        if $crate::util::log::enabled(module_path!(), $level) {
+            $crate::util::log::write(module_path!(), $level, format_args!($($arg)+));
+        }
+    };
+}
+
+macro_rules! log_enabled {
+    ($level:expr) => {
+        $crate::util::log::enabled(module_path!(), $level)
+    };
+}
+
+#[allow(unused_macros)]
+macro_rules! error {
+    ($($arg:tt)+) => { log!($crate::util::log::Level::Error, $($arg)+) };
+}
+
+#[allow(unused_macros)]
+macro_rules! warn {
+    ($($arg:tt)+) => { log!($crate::util::log::Level::Warn, $($arg)+) };
+}
+
+macro_rules! info {
+    ($($arg:tt)+) => { log!($crate::util::log::Level::Info, $($arg)+) };
+}
+
+macro_rules! debug {
+    ($($arg:tt)+) => { log!($crate::util::log::Level::Debug, $($arg)+) };
+}
+
+macro_rules! trace {
+    ($($arg:tt)+) => { log!($crate::util::log::Level::Trace, $($arg)+) };
+}
+
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope.
+    use super::*;
+
+    #[test]
+    fn test_filter() {
+        let filter = Filter::parse("info,day13=trace,day_10=off", Level::Warn).unwrap();
+        assert_eq!(filter.level, Level::Info);
+        assert!(filter.enabled("day_18", Level::Info));
+        assert!(!filter.enabled("day_18", Level::Debug));
+        assert!(filter.enabled("day_13", Level::Trace));
+        assert!(!filter.enabled("day_10", Level::Error));
+        assert!(!filter.enabled("day_18", Level::Off));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(Filter::parse("loud", Level::Warn).is_err());
+        assert!(Filter::parse("day13=everything", Level::Warn).is_err());
+        assert_eq!(Filter::parse("", Level::Debug), Ok(Filter::new(Level::Debug)));
+    }
+
+    #[test]
+    fn test_target() {
+        assert_eq!(target("advent_of_code_2019::util::day_13"), "day_13");
+        assert_eq!(normalize_target("day7"), "day_07");
+        assert_eq!(normalize_target("int_code_computer"), "int_code_computer");
+        assert!(!log_enabled!(Level::Info));
+    }
+}
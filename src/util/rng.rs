@@ -0,0 +1,22 @@
+// xorshift64* - small and deterministic so a failing seed can be replayed
+pub struct Rng {
+    state:u64,
+}
+
+impl Rng {
+    pub fn new(seed:u64) -> Rng {
+        Rng{state:(seed ^ 0x9E37_79B9_7F4A_7C15) | 1}
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    // Value in low..high
+    pub fn range(&mut self, low:i64, high:i64) -> i64 {
+        low + (self.next_u64() % ((high - low) as u64)) as i64
+    }
+}
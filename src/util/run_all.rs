@@ -0,0 +1,213 @@
+use crate::util::*;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Status {
+    Solved(Answer),
+    Panicked(String),
+    NotImplemented,
+    MissingInput,
+}
+
+#[derive(Debug, Clone)]
+pub struct RunResult {
+    pub day:u8,
+    pub part:Part,
+    pub status:Status,
+    pub elapsed:Duration,
+}
+
+// Runs both parts of every solver with its input from the directory, on up to
+// `threads` threads. Results are ordered by day and part.
+pub fn run_all(solvers:&[&'static dyn Solver], dir:&str, threads:usize) -> Vec<RunResult> {
+    let jobs = Mutex::new(solvers.iter()
+        .flat_map(|solver| [Part::Part1, Part::Part2].iter().map(move |part| (*solver, *part)))
+        .collect::<Vec<_>>()
+        .into_iter());
+    let results = Mutex::new(vec![]);
+
+    std::thread::scope(|scope| {
+        for _ in 0..threads.max(1) {
+            scope.spawn(|| loop {
+                let next = jobs.lock().unwrap().next();
+                let (solver, part) = match next {
+                    Some(job) => job,
+                    None => break,
+                };
+                let result = run_part(solver, part, dir);
+                results.lock().unwrap().push(result);
+            });
+        }
+    });
+
+    let mut results = results.into_inner().unwrap();
+    results.sort_by_key(|result| (result.day, result.part == Part::Part2));
+    results
+}
+
+fn run_part(solver:&dyn Solver, part:Part, dir:&str) -> RunResult {
+    if !solver.implements(part) {
+        return RunResult{day:solver.day(), part, status:Status::NotImplemented, elapsed:Duration::ZERO};
+    }
+    match find_input(dir, solver.day()).and_then(|path| std::fs::read_to_string(path).ok()) {
+        None => RunResult{day:solver.day(), part, status:Status::MissingInput, elapsed:Duration::ZERO},
+        Some(input) => run_with_input(solver, part, input),
+    }
+}
+
+// Solves one part, timing it and turning a panic into a status
+pub fn run_with_input(solver:&dyn Solver, part:Part, input:String) -> RunResult {
+    let start = Instant::now();
+    let status = if !solver.implements(part) {
+        Status::NotImplemented
+    } else {
+        match panic::catch_unwind(AssertUnwindSafe(|| solver.solve(input, part))) {
+            Ok(answer) => Status::Solved(answer),
+            Err(payload) => Status::Panicked(panic_message(payload)),
+        }
+    };
+
+    RunResult{day:solver.day(), part, status, elapsed:start.elapsed()}
+}
+
+fn panic_message(payload:Box<dyn std::any::Any + Send>) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        String::from(*message)
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        String::from("unknown panic")
+    }
+}
+
+pub fn format_table(results:&[RunResult], total:Duration) -> String {
+    let mut output = format!("{:>3} {:>4}  {:<24} {:>12}  {}\n", "day", "part", "answer", "time", "status");
+
+    for result in results {
+        let (answer, status) = match &result.status {
+            Status::Solved(Answer::Art(art)) => (format!("<art, {} lines>", art.lines().count()), String::from("ok")),
+            Status::Solved(answer) => (answer.to_string(), String::from("ok")),
+            Status::Panicked(message) => (String::new(), format!("panicked: {}", message)),
+            Status::NotImplemented => (String::new(), String::from("not implemented")),
+            Status::MissingInput => (String::new(), String::from("no input")),
+        };
+        output.push_str(format!("{:>3} {:>4}  {:<24} {:>12}  {}\n",
+                                result.day, result.part, answer, format_duration(result.elapsed), status).as_str());
+    }
+    output.push_str(format!("{:>3} {:>4}  {:<24} {:>12}\n", "", "", "total", format_duration(total)).as_str());
+
+    output
+}
+
+// One JSON object per line and result:
+//
+//   {"day":7,"part":1,"status":"solved","answer":338603,"duration_ms":2.417}
+//
+// Numbers are JSON numbers and other answers strings. Failed parts have no
+// answer, a panic has its message as "error".
+pub fn format_json(results:&[RunResult]) -> String {
+    let mut output = String::new();
+
+    for result in results {
+        let (status, field) = match &result.status {
+            Status::Solved(Answer::Number(number)) => ("solved", format!(",\"answer\":{}", number)),
+            Status::Solved(answer) => ("solved", format!(",\"answer\":{}", json_string(&answer.to_string()))),
+            Status::Panicked(message) => ("panicked", format!(",\"error\":{}", json_string(message))),
+            Status::NotImplemented => ("not_implemented", String::new()),
+            Status::MissingInput => ("missing_input", String::new()),
+        };
+        output.push_str(format!("{{\"day\":{},\"part\":{},\"status\":\"{}\"{},\"duration_ms\":{:.3}}}\n",
+                                result.day, result.part, status, field, result.elapsed.as_secs_f64() * 1000.0).as_str());
+    }
+
+    output
+}
+
+fn json_string(text:&str) -> String {
+    let mut output = String::from("\"");
+    for ch in text.chars() {
+        match ch {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\t' => output.push_str("\\t"),
+            ch if (ch as u32) < 0x20 => output.push_str(format!("\\u{:04x}", ch as u32).as_str()),
+            ch => output.push(ch),
+        }
+    }
+    output.push('"');
+    output
+}
+
+pub fn format_duration(duration:Duration) -> String {
+    let millis = duration.as_secs_f64() * 1000.0;
+    if millis < 1.0 {
+        format!("{:.1} µs", millis * 1000.0)
+    } else if millis < 1000.0 {
+        format!("{:.1} ms", millis)
+    } else {
+        format!("{:.2} s", millis / 1000.0)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope.
+    use super::*;
+
+    // Unique per process so that concurrent test runs do not share the directory
+    fn temp_dir(name:&str) -> String {
+        let dir = std::env::temp_dir().join(format!("{}_{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn test_run_all() {
+        let solvers = [find_solver(1).unwrap(), find_solver(4).unwrap(), find_solver(21).unwrap()];
+        let results = run_all(&solvers, ".", 2);
+
+        let statuses:Vec<(u8, Part, bool)> = results.iter()
+            .map(|result| (result.day, result.part, matches!(result.status, Status::Solved(_))))
+            .collect();
+        assert_eq!(statuses, vec![(1, Part::Part1, true), (1, Part::Part2, true), (4, Part::Part1, true),
+                                  (4, Part::Part2, true), (21, Part::Part1, false), (21, Part::Part2, false)]);
+        assert_eq!(results[4].status, Status::NotImplemented);
+
+        let table = format_table(&results, Duration::from_millis(1500));
+        assert_eq!(table.lines().count(), 8);
+        let total = table.lines().last().unwrap();
+        assert!(total.contains("total") && total.ends_with("1.50 s"));
+    }
+
+    #[test]
+    fn test_json() {
+        let results = [
+            RunResult{day:7, part:Part::Part1, status:Status::Solved(Answer::Number(338603)), elapsed:Duration::from_micros(2417)},
+            RunResult{day:8, part:Part::Part2, status:Status::Solved(Answer::Art(String::from("# \"\n #"))), elapsed:Duration::ZERO},
+            RunResult{day:9, part:Part::Part1, status:Status::Panicked(String::from("tab\there")), elapsed:Duration::ZERO},
+            RunResult{day:21, part:Part::Part1, status:Status::NotImplemented, elapsed:Duration::ZERO},
+        ];
+
+        assert_eq!(format_json(&results),
+                   "{\"day\":7,\"part\":1,\"status\":\"solved\",\"answer\":338603,\"duration_ms\":2.417}\n\
+                    {\"day\":8,\"part\":2,\"status\":\"solved\",\"answer\":\"# \\\"\\n #\",\"duration_ms\":0.000}\n\
+                    {\"day\":9,\"part\":1,\"status\":\"panicked\",\"error\":\"tab\\there\",\"duration_ms\":0.000}\n\
+                    {\"day\":21,\"part\":1,\"status\":\"not_implemented\",\"duration_ms\":0.000}\n");
+    }
+
+    #[test]
+    fn test_panics_and_missing_inputs() {
+        let dir = temp_dir("run_all_test");
+        std::fs::write(std::path::Path::new(&dir).join("input_1.txt"), "not a number\n").unwrap();
+
+        let results = run_all(&[find_solver(1).unwrap(), find_solver(2).unwrap()], &dir, 1);
+        std::fs::remove_dir_all(dir).unwrap();
+
+        assert!(matches!(results[0].status, Status::Panicked(_)));
+        assert_eq!(results[2].status, Status::MissingInput);
+    }
+}
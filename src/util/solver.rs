@@ -0,0 +1,206 @@
+use crate::util::*;
+use std::convert::TryFrom;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InputKind {
+    // Comma separated Intcode program
+    Intcode,
+    // One number per line
+    Numbers,
+    Lines,
+    // Character map, one row per line
+    Grid,
+    // A single line of text
+    Text,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Answer {
+    Number(i64),
+    Text(String),
+    // Multi line picture such as letters painted by a robot
+    Art(String),
+}
+
+impl Answer {
+    // Pictures are recorded without the newline that ends the last row
+    pub fn art(picture:String) -> Answer {
+        Answer::Art(String::from(picture.trim_end_matches('\n')))
+    }
+}
+
+// Days count with whatever integer type suits the puzzle
+macro_rules! number_answer {
+    ($($number:ty),*) => {
+        $(impl From<$number> for Answer {
+            fn from(number:$number) -> Answer {
+                Answer::Number(i64::try_from(number).expect("answer does not fit in an i64"))
+            }
+        })*
+    };
+}
+
+number_answer!(i32, i64, u32, u64, usize);
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Answer::Number(number) => write!(f, "{}", number),
+            Answer::Text(text) => write!(f, "{}", text),
+            Answer::Art(art) => write!(f, "{}", art),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SolveError {
+    UnknownDay(u8),
+    NotImplemented(u8, Part),
+}
+
+impl fmt::Display for SolveError {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SolveError::UnknownDay(day) => write!(f, "there is no solution for day {}", day),
+            SolveError::NotImplemented(day, part) => write!(f, "day {} part {} is not implemented", day, part),
+        }
+    }
+}
+
+pub trait Solver : Sync {
+    fn day(&self) -> u8;
+    fn title(&self) -> &str;
+    fn parts(&self) -> &[Part];
+    fn input_kind(&self) -> InputKind;
+    fn solve(&self, input:String, part:Part) -> Answer;
+
+    fn implements(&self, part:Part) -> bool {
+        self.parts().contains(&part)
+    }
+}
+
+// A day module, its `solve` function and what is known about it
+pub struct Day {
+    day:u8,
+    title:&'static str,
+    parts:&'static [Part],
+    input_kind:InputKind,
+    solve:fn(String, Part) -> Answer,
+}
+
+impl Solver for Day {
+    fn day(&self) -> u8 {
+        self.day
+    }
+
+    fn title(&self) -> &str {
+        self.title
+    }
+
+    fn parts(&self) -> &[Part] {
+        self.parts
+    }
+
+    fn input_kind(&self) -> InputKind {
+        self.input_kind
+    }
+
+    fn solve(&self, input:String, part:Part) -> Answer {
+        (self.solve)(input, part)
+    }
+}
+
+const BOTH:&[Part] = &[Part::Part1, Part::Part2];
+const FIRST:&[Part] = &[Part::Part1];
+
+// Only parts that give the right answer are listed. Day 13 part 2 loses the
+// ball with blocks left and reports a score of 0.
+static DAYS:[Day;23] = [
+    Day{day:1, title:"The Tyranny of the Rocket Equation", parts:BOTH, input_kind:InputKind::Numbers, solve:day_01::solve},
+    Day{day:2, title:"1202 Program Alarm", parts:BOTH, input_kind:InputKind::Intcode, solve:day_02::solve},
+    Day{day:3, title:"Crossed Wires", parts:BOTH, input_kind:InputKind::Lines, solve:day_03::solve},
+    Day{day:4, title:"Secure Container", parts:BOTH, input_kind:InputKind::Text, solve:day_04::solve},
+    Day{day:5, title:"Sunny with a Chance of Asteroids", parts:BOTH, input_kind:InputKind::Intcode, solve:day_05::solve},
+    Day{day:6, title:"Universal Orbit Map", parts:BOTH, input_kind:InputKind::Lines, solve:day_06::solve},
+    Day{day:7, title:"Amplification Circuit", parts:BOTH, input_kind:InputKind::Intcode, solve:day_07::solve},
+    Day{day:8, title:"Space Image Format", parts:BOTH, input_kind:InputKind::Text, solve:day_08::solve},
+    Day{day:9, title:"Sensor Boost", parts:BOTH, input_kind:InputKind::Intcode, solve:day_09::solve},
+    Day{day:10, title:"Monitoring Station", parts:BOTH, input_kind:InputKind::Grid, solve:day_10::solve},
+    Day{day:11, title:"Space Police", parts:BOTH, input_kind:InputKind::Intcode, solve:day_11::solve},
+    Day{day:12, title:"The N-Body Problem", parts:BOTH, input_kind:InputKind::Lines, solve:day_12::solve},
+    Day{day:13, title:"Care Package", parts:FIRST, input_kind:InputKind::Intcode, solve:day_13::solve},
+    Day{day:14, title:"Space Stoichiometry", parts:BOTH, input_kind:InputKind::Lines, solve:day_14::solve},
+    Day{day:15, title:"Oxygen System", parts:BOTH, input_kind:InputKind::Intcode, solve:day_15::solve},
+    Day{day:16, title:"Flawed Frequency Transmission", parts:BOTH, input_kind:InputKind::Text, solve:day_16::solve},
+    Day{day:17, title:"Set and Forget", parts:FIRST, input_kind:InputKind::Intcode, solve:day_17::solve},
+    Day{day:18, title:"Many-Worlds Interpretation", parts:BOTH, input_kind:InputKind::Grid, solve:day_18::solve},
+    Day{day:19, title:"Tractor Beam", parts:BOTH, input_kind:InputKind::Intcode, solve:day_19::solve},
+    Day{day:20, title:"Donut Maze", parts:BOTH, input_kind:InputKind::Grid, solve:day_20::solve},
+    Day{day:21, title:"Springdroid Adventure", parts:&[], input_kind:InputKind::Intcode, solve:day_21::solve},
+    Day{day:23, title:"Category Six", parts:BOTH, input_kind:InputKind::Intcode, solve:day_23::solve},
+    Day{day:24, title:"Planet of Discord", parts:BOTH, input_kind:InputKind::Grid, solve:day_24::solve},
+];
+
+// All registered solvers ordered by day
+pub fn solvers() -> Vec<&'static dyn Solver> {
+    DAYS.iter().map(|day| day as &dyn Solver).collect()
+}
+
+pub fn find_solver(day:u8) -> Option<&'static dyn Solver> {
+    solvers().into_iter().find(|solver| solver.day() == day)
+}
+
+pub fn get_solution(day:u8, part:Part, input:String) -> Result<Answer, SolveError> {
+    let solver = find_solver(day).ok_or(SolveError::UnknownDay(day))?;
+    if !solver.implements(part) {
+        return Err(SolveError::NotImplemented(day, part));
+    }
+    Ok(solver.solve(input, part))
+}
+
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope.
+    use super::*;
+
+    #[test]
+    fn test_registry() {
+        let days:Vec<u8> = solvers().iter().map(|solver| solver.day()).collect();
+        assert!(days.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(days.len(), 23);
+
+        let solver = find_solver(17).unwrap();
+        assert_eq!(solver.title(), "Set and Forget");
+        assert!(solver.implements(Part::Part1));
+        assert!(!solver.implements(Part::Part2));
+        assert!(find_solver(22).is_none());
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(get_solution(22, Part::Part1, String::new()), Err(SolveError::UnknownDay(22)));
+        assert_eq!(get_solution(17, Part::Part2, String::new()), Err(SolveError::NotImplemented(17, Part::Part2)));
+        assert_eq!(SolveError::NotImplemented(17, Part::Part2).to_string(), "day 17 part 2 is not implemented");
+    }
+
+    #[test]
+    fn test_answers() {
+        assert_eq!(Answer::from(-42), Answer::Number(-42));
+        assert_eq!(Answer::from(12_usize), Answer::Number(12));
+        assert_eq!(Answer::art(String::from("# #\n ##\n")), Answer::Art(String::from("# #\n ##")));
+        assert_eq!(Answer::Text(String::from("01029498")).to_string(), "01029498");
+    }
+
+    #[test]
+    fn test_solve_day_01() {
+        let input = std::fs::read_to_string("input_1.txt").unwrap();
+        let answer = get_solution(1, Part::Part1, input).unwrap();
+        assert!(matches!(answer, Answer::Number(_)));
+
+        // The FFT message keeps its leading zeros as text
+        let answer = get_solution(16, Part::Part1, String::from("80871224585914546619083218645595")).unwrap();
+        assert_eq!(answer, Answer::Text(String::from("24176176")));
+    }
+}
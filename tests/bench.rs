@@ -0,0 +1,13 @@
+use advent_of_code_2019::*;
+use advent_of_code_2019::runner::*;
+
+#[global_allocator]
+static ALLOCATOR:CountingAllocator = CountingAllocator;
+
+#[test]
+fn test_allocations_are_counted() {
+    let input = read_input(&InputSource::Discover(String::from(".")), 1).unwrap();
+    let stats = bench(find_solver(1).unwrap(), Part::Part1, &input, 0, 3);
+    assert_eq!(stats.runs, 3);
+    assert!(stats.allocations.unwrap() > 0);
+}
@@ -0,0 +1,59 @@
+use advent_of_code_2019::intcode::*;
+use advent_of_code_2019::intcode::cache::CachedProgram;
+use advent_of_code_2019::intcode::scheduler::{Scheduler, State};
+
+fn load(path:&str) -> Vec<i64> {
+    std::fs::read_to_string(path).unwrap()
+        .split(',')
+        .map(|op| op.trim().parse().unwrap())
+        .collect()
+}
+
+#[test]
+fn test_gravity_assist() {
+    let mut program = Program::new(load("input_2.txt"), None);
+    program.set_memory(1, 12);
+    program.set_memory(2, 2);
+    assert_eq!(program.run_until_stop(), StopReason::Halted);
+    assert_eq!(program.get_memory(0), 3760627);
+}
+
+#[test]
+fn test_boost_keycode() {
+    let mut program = Program::new(load("input_09.txt"), Some(vec![1]));
+    assert_eq!(program.run_until_stop(), StopReason::Halted);
+    assert_eq!(program.take_output(), vec![2775723069]);
+}
+
+#[test]
+fn test_amplifier_feedback_loop() {
+    // Example from the puzzle, thrust is 139629729 for phases 9,8,7,6,5
+    let opcodes = vec![3,26,1001,26,-4,26,3,27,1002,27,2,27,1,27,26,27,4,27,1001,28,-1,28,1005,28,6,99,0,0,5];
+    let mut scheduler = Scheduler::new(10);
+    let amplifiers:Vec<usize> = [9, 8, 7, 6, 5].iter()
+        .map(|phase| scheduler.spawn(Program::new(opcodes.clone(), Some(vec![*phase]))))
+        .collect();
+    scheduler.send(amplifiers[0], 0);
+
+    let mut thrust = 0;
+    scheduler.run_until_idle(|scheduler, pid, outputs| {
+        let next = (pid + 1) % amplifiers.len();
+        for value in outputs {
+            if next == 0 {
+                thrust = value;
+            }
+            scheduler.send(amplifiers[next], value);
+        }
+    });
+
+    assert_eq!(scheduler.state(amplifiers[4]), &State::Halted);
+    assert_eq!(thrust, 139629729);
+}
+
+#[test]
+fn test_cached_tractor_beam() {
+    let mut drone = CachedProgram::new(load("input_19.txt"));
+    assert_eq!(drone.run(&[0, 0]), Ok(vec![1]));
+    assert_eq!(drone.run(&[0, 0]), Ok(vec![1]));
+    assert_eq!((drone.hits, drone.misses), (1, 1));
+}
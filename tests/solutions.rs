@@ -0,0 +1,44 @@
+use advent_of_code_2019::*;
+use advent_of_code_2019::runner::*;
+
+// Too slow for a debug build, run with `cargo test -- --ignored`
+const SLOW_DAYS:[u8;2] = [12, 19];
+
+fn check_days(days:&[u8]) {
+    let answers = Answers::load(ANSWERS_FILE).unwrap();
+    let selected:Vec<&'static dyn Solver> = solvers().into_iter()
+        .filter(|solver| days.contains(&solver.day()))
+        .collect();
+    let threads = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+    let results = run_all(&selected, ".", threads);
+
+    let (report, failures) = format_checks(&results, &answers);
+    assert_eq!(failures, 0, "\n{}", report);
+}
+
+#[test]
+fn test_registry_covers_bundled_inputs() {
+    for solver in solvers().into_iter().filter(|solver| !solver.parts().is_empty()) {
+        assert!(find_input(".", solver.day()).is_some(), "no input for day {}", solver.day());
+    }
+    assert_eq!(find_solver(7).unwrap().input_kind(), InputKind::Intcode);
+}
+
+#[test]
+fn test_recorded_answers() {
+    let days:Vec<u8> = solvers().iter().map(|solver| solver.day()).filter(|day| !SLOW_DAYS.contains(day)).collect();
+    check_days(&days);
+}
+
+#[test]
+#[ignore]
+fn test_recorded_answers_slow_days() {
+    check_days(&SLOW_DAYS);
+}
+
+#[test]
+fn test_get_solution() {
+    let input = read_input(&InputSource::Discover(String::from(".")), 6).unwrap();
+    assert_eq!(get_solution(6, Part::Part2, input), Ok(Answer::Number(514)));
+    assert_eq!(get_solution(21, Part::Part1, String::new()), Err(SolveError::NotImplemented(21, Part::Part1)));
+}